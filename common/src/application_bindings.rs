@@ -2,27 +2,217 @@ use std::collections::HashMap;
 
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+#[derive(Debug, Deserialize, Serialize, Clone, Default, PartialEq)]
 pub struct ApplicationBindings {
     #[serde(flatten)]
     pub profiles: HashMap<String, InteractionProfileBindings>,
 }
 
-#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+#[derive(Debug, Deserialize, Serialize, Clone, Default, PartialEq)]
 pub struct InteractionProfileBindings {
     #[serde(flatten)]
     pub action_sets: HashMap<String, ActionSetBindings>,
 }
 
-#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+#[derive(Debug, Deserialize, Serialize, Clone, Default, PartialEq)]
 pub struct ActionSetBindings {
     #[serde(flatten)]
     pub actions: HashMap<String, ActionBindings>,
 }
 
-#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+#[derive(Debug, Deserialize, Serialize, Clone, Default, PartialEq)]
 pub struct ActionBindings {
     pub bindings: Vec<String>,
+    ///Extra behaviour applied to individual bindings, keyed by the binding path
+    #[serde(skip_serializing_if = "HashMap::is_empty", default)]
+    pub modifiers: HashMap<String, BindingModifiers>,
+    ///How the states of the bindings are combined when more than one is active
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub conflict_policy: Option<ConflictPolicy>,
+    ///Changes made to haptic feedback before it is sent to the bindings of an output action
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub haptics: Option<HapticRules>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ConflictPolicy {
+    ///What the spec requires: OR for booleans, the largest absolute value for floats and the longest vector for Vector2fs
+    Default,
+    ///Float and Vector2f states are added together
+    Sum,
+    ///Float and Vector2f states are added together then clamped to a length of 1.0
+    ClampedSum,
+    ///The first binding (in the order they are listed) which is not at rest wins, bindings of different profiles are ordered by profile name
+    Priority,
+    ///The binding which changed most recently wins
+    LastChanged,
+}
+
+impl ConflictPolicy {
+    ///Sum, ClampedSum and Priority combine values which boolean actions don't have
+    pub fn applies_to_booleans(&self) -> bool {
+        match self {
+            ConflictPolicy::Default | ConflictPolicy::LastChanged => true,
+            ConflictPolicy::Sum | ConflictPolicy::ClampedSum | ConflictPolicy::Priority => false,
+        }
+    }
+}
+
+impl Default for ConflictPolicy {
+    fn default() -> Self {
+        ConflictPolicy::Default
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Default, PartialEq)]
+pub struct BindingModifiers {
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub ramp: Option<Ramp>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub decomposition: Option<Decomposition>,
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub haptic_triggers: Vec<HapticTrigger>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub pose_offset: Option<PoseOffset>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub analog_threshold: Option<AnalogThreshold>,
+    ///The subaction path of the action the binding feeds (e.g. /user/hand/left) when it differs from the user path of the binding
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub subaction_path: Option<String>,
+}
+
+///Used when a float action is bound to a boolean component (e.g. click) to move smoothly between 0.0 and 1.0 rather than jumping
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq)]
+pub struct Ramp {
+    ///Seconds taken to go from 0.0 to 1.0 while the input is held
+    pub ramp_up: f32,
+    ///Seconds taken to go from 1.0 to 0.0 once the input is released
+    pub ramp_down: f32,
+}
+
+///Used when a boolean or float action is bound to a 2D input (e.g. /input/thumbstick) to reduce the position to a single value
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Decomposition {
+    ///The distance of the position from the center, from 0.0 to 1.0
+    Magnitude,
+    ///1.0 while the position is inside the sector, otherwise 0.0 (e.g. for radial menus)
+    Sector {
+        ///Angle in degrees of the middle of the sector, counter-clockwise from the right (+x)
+        center: f32,
+        ///Angle in degrees covered by the sector
+        width: f32,
+        ///Distance from the center the position must pass before the sector can activate
+        deadzone: f32,
+    },
+    ///A single axis of the position
+    Axis {
+        axis: Axis,
+        #[serde(default)]
+        sign: AxisSign,
+    },
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Axis {
+    X,
+    Y,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AxisSign {
+    ///Pass the axis through unchanged, from -1.0 to 1.0
+    Both,
+    ///Only the positive half of the axis, from 0.0 to 1.0
+    Positive,
+    ///Only the negative half of the axis, flipped to go from 0.0 to 1.0
+    Negative,
+}
+
+impl Default for AxisSign {
+    fn default() -> Self {
+        AxisSign::Both
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Default, PartialEq)]
+pub struct HapticRules {
+    ///Drop all haptic feedback sent to the action
+    #[serde(skip_serializing_if = "std::ops::Not::not", default)]
+    pub mute: bool,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub route: Option<HapticRoute>,
+    ///Multiplier applied to the amplitude, the result is clamped to 1.0
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub amplitude_scale: Option<f32>,
+    ///Multiplier applied to the duration
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub duration_scale: Option<f32>,
+    ///Shortest duration in seconds, also replaces XR_MIN_HAPTIC_DURATION
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub min_duration: Option<f32>,
+    ///Longest duration in seconds
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub max_duration: Option<f32>,
+    ///Frequency in Hz used instead of the one requested by the application
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub frequency: Option<f32>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum HapticRoute {
+    ///The device the haptic is bound to
+    Same,
+    ///The opposite hand, devices with a single user path are left unchanged
+    Swap,
+    ///Every user path of the device the haptic is bound to
+    Both,
+}
+
+impl Default for HapticRoute {
+    fn default() -> Self {
+        HapticRoute::Same
+    }
+}
+
+///A haptic pulse played by the layer when something happens to a boolean binding (e.g. a click when it is pressed)
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct HapticTrigger {
+    pub condition: TriggerCondition,
+    ///The haptic output to pulse, defaults to the one on the same user path as the binding (e.g. /user/hand/right/output/haptic)
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub output: Option<String>,
+    ///Seconds
+    pub duration: f32,
+    pub amplitude: f32,
+    ///Hz, 0.0 leaves it to the runtime
+    #[serde(default)]
+    pub frequency: f32,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum TriggerCondition {
+    Press,
+    Release,
+    Change,
+    ///Fires once the binding has been held for the given number of seconds
+    LongPress { seconds: f32 },
+}
+
+///Moves a pose binding relative to itself, used to correct grip / aim poses which do not match the controller a game was made for
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Default)]
+pub struct PoseOffset {
+    ///Meters
+    #[serde(default)]
+    pub position: [f32; 3],
+    ///Degrees around the x, y and z axes, applied in that order
+    #[serde(default)]
+    pub rotation: [f32; 3],
 }
 
 pub struct Binding {
@@ -30,11 +220,23 @@ pub struct Binding {
     pub analog_threshold: Option<AnalogThreshold>,
 }
 
+///Used when a boolean action is bound to an analog input, the state turns on above on_threshold and only turns off again below off_threshold
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq)]
 pub struct AnalogThreshold {
     pub on_threshold: f32,
     pub off_threshold: f32,
 }
 
+impl AnalogThreshold {
+    pub fn is_on(&self, was_on: bool, value: f32) -> bool {
+        if was_on {
+            value > self.off_threshold
+        } else {
+            value > self.on_threshold
+        }
+    }
+}
+
 #[test]
 fn test_json(){
     let mut profiles = ApplicationBindings {
@@ -50,19 +252,69 @@ fn test_json(){
             let mut set = ActionSetBindings {
                 actions: HashMap::new(),
             };
-            set.actions.insert("pose_grip".to_owned(), ActionBindings{bindings: vec!["/user/hand/left/input/grip/pose".to_owned(), "/user/hand/right/input/grip/pose".to_owned()]});
+            set.actions.insert("pose_grip".to_owned(), ActionBindings{bindings: vec!["/user/hand/left/input/grip/pose".to_owned(), "/user/hand/right/input/grip/pose".to_owned()], modifiers: {
+                let mut modifiers = HashMap::new();
+                modifiers.insert("/user/hand/right/input/grip/pose".to_owned(), BindingModifiers {
+                    pose_offset: Some(PoseOffset { position: [0.0, 0.0, -0.02], rotation: [-30.0, 0.0, 0.0] }),
+                    ..Default::default()
+                });
+                modifiers
+            }, ..Default::default()});
             set
         });
         profile.action_sets.insert("gameplay".to_owned(), {
             let mut set = ActionSetBindings {
                 actions: HashMap::new(),
             };
-            set.actions.insert("use".to_owned(), ActionBindings{bindings: vec!["/user/hand/left/input/trigger/value".to_owned()]});
-            set.actions.insert("attack".to_owned(), ActionBindings{bindings: vec!["/user/hand/right/input/trigger/value".to_owned()]});
+            set.actions.insert("use".to_owned(), ActionBindings{bindings: vec!["/user/hand/left/input/trigger/value".to_owned(), "/user/hand/left/input/thumbstick/y".to_owned()], conflict_policy: Some(ConflictPolicy::ClampedSum), ..Default::default()});
+            set.actions.insert("jump".to_owned(), ActionBindings{bindings: vec!["/user/hand/left/input/thumbstick".to_owned()], modifiers: {
+                let mut modifiers = HashMap::new();
+                modifiers.insert("/user/hand/left/input/thumbstick".to_owned(), BindingModifiers {
+                    decomposition: Some(Decomposition::Axis { axis: Axis::Y, sign: AxisSign::Positive }),
+                    subaction_path: Some("/user/hand/right".to_owned()),
+                    ..Default::default()
+                });
+                modifiers
+            }, ..Default::default()});
+            set.actions.insert("haptic".to_owned(), ActionBindings{bindings: vec!["/user/hand/left/output/haptic".to_owned()], haptics: Some(HapticRules {
+                route: Some(HapticRoute::Both),
+                amplitude_scale: Some(0.5),
+                max_duration: Some(0.2),
+                ..Default::default()
+            }), ..Default::default()});
+            set.actions.insert("attack".to_owned(), ActionBindings{bindings: vec!["/user/hand/right/input/trigger/value".to_owned(), "/user/hand/right/input/a/click".to_owned()], modifiers: {
+                let mut modifiers = HashMap::new();
+                modifiers.insert("/user/hand/right/input/trigger/value".to_owned(), BindingModifiers {
+                    analog_threshold: Some(AnalogThreshold { on_threshold: 0.75, off_threshold: 0.5 }),
+                    ..Default::default()
+                });
+                modifiers.insert("/user/hand/right/input/a/click".to_owned(), BindingModifiers {
+                    ramp: Some(Ramp { ramp_up: 0.5, ramp_down: 0.25 }),
+                    haptic_triggers: vec![HapticTrigger {
+                        condition: TriggerCondition::LongPress { seconds: 1.0 },
+                        output: None,
+                        duration: 0.05,
+                        amplitude: 0.8,
+                        frequency: 0.0,
+                    }],
+                    ..Default::default()
+                });
+                modifiers
+            }, ..Default::default()});
             set
         });
         profile
     });
 
-    println!("{}", serde_json::to_string_pretty(&profiles).unwrap());
+    let json = serde_json::to_value(&profiles).unwrap();
+    let attack = &json["/interaction_profiles/oculus/touch_controller"]["gameplay"]["attack"];
+    assert_eq!(attack["modifiers"]["/user/hand/right/input/trigger/value"]["analog_threshold"]["on_threshold"], 0.75);
+    assert_eq!(attack["modifiers"]["/user/hand/right/input/a/click"]["haptic_triggers"][0]["condition"]["long_press"]["seconds"], 1.0);
+    let jump = &json["/interaction_profiles/oculus/touch_controller"]["gameplay"]["jump"];
+    assert_eq!(jump["modifiers"]["/user/hand/left/input/thumbstick"]["subaction_path"], "/user/hand/right");
+    //Unset modifiers are left out of the file
+    assert!(attack["modifiers"]["/user/hand/right/input/trigger/value"].get("ramp").is_none());
+
+    let round_trip: ApplicationBindings = serde_json::from_str(&serde_json::to_string_pretty(&profiles).unwrap()).unwrap();
+    assert_eq!(round_trip, profiles);
 }
\ No newline at end of file
@@ -0,0 +1,185 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+///Per application settings which are not tied to an action
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+pub struct ApplicationSettings {
+    ///Filters applied to the poses of action spaces, keyed by top level user path (e.g. /user/hand/left)
+    #[serde(skip_serializing_if = "HashMap::is_empty", default)]
+    pub pose_filters: HashMap<String, PoseFilter>,
+    ///Moves the application's STAGE and LOCAL spaces
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub playspace: Option<PlayspaceOffset>,
+    ///Meters the LOCAL origin is above the floor, used for emulated LOCAL_FLOOR spaces instead of measuring it from STAGE
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub local_floor_height: Option<f32>,
+    ///Lets the user move the playspace by holding a chord and moving their hand
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub space_drag: Option<SpaceDrag>,
+    ///Presents the hand controllers as an xbox controller on /user/gamepad
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub gamepad_emulation: Option<GamepadEmulation>,
+    ///Top level user paths (e.g. /user/hand/left) where every device the runtime reports input from is used at once, not just the current interaction profile
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub blend_devices: Vec<String>,
+}
+
+///Smooths out jitter in tracked poses at the cost of some latency
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum PoseFilter {
+    ///Moves a fixed fraction of the way towards the new pose, frame rate independent
+    Exponential {
+        ///Seconds taken to cover ~63% of the distance to the new pose
+        time_constant: f32,
+    },
+    ///The 1€ filter, which smooths heavily while still and less as the pose moves faster
+    OneEuro {
+        ///Cutoff frequency in Hz used while still, lower values remove more jitter
+        min_cutoff: f32,
+        ///How quickly the cutoff rises with speed, higher values reduce lag during fast movement
+        beta: f32,
+        ///Cutoff frequency in Hz used to smooth the speed
+        #[serde(default = "default_derivative_cutoff")]
+        derivative_cutoff: f32,
+    },
+}
+
+fn default_derivative_cutoff() -> f32 {
+    1.0
+}
+
+///Where the application's STAGE and LOCAL spaces sit within the runtime's, e.g. to fix the floor height or let a seated user play a standing game
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Default)]
+pub struct PlayspaceOffset {
+    ///Meters along the x and z axes
+    #[serde(default)]
+    pub translation: [f32; 2],
+    ///Meters the origin is raised by
+    #[serde(default)]
+    pub height: f32,
+    ///Degrees counter-clockwise around the up axis
+    #[serde(default)]
+    pub yaw: f32,
+}
+
+///Drags the playspace with a hand, measured in the runtime's LOCAL space
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct SpaceDrag {
+    ///The interaction profile the chord belongs to
+    pub interaction_profile: String,
+    ///Inputs which must all be held while dragging (e.g. /user/hand/left/input/menu/click)
+    pub chord: Vec<String>,
+    ///The hand whose grip pose moves the playspace (e.g. /user/hand/left)
+    pub hand: String,
+    #[serde(default)]
+    pub mode: SpaceDragMode,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SpaceDragMode {
+    ///The playspace follows the hand's movement
+    Translate,
+    ///The playspace turns around the hand as it twists
+    Rotate,
+    Both,
+}
+
+impl Default for SpaceDragMode {
+    fn default() -> Self {
+        SpaceDragMode::Both
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq, Default)]
+pub struct GamepadEmulation {
+    ///Gamepad subpaths (e.g. /input/a/click) to the hand inputs feeding them (e.g. /user/hand/right/input/a/click), the default mapping is used when empty
+    #[serde(skip_serializing_if = "HashMap::is_empty", default)]
+    pub mapping: HashMap<String, Vec<String>>,
+}
+
+impl GamepadEmulation {
+    pub fn mapping(&self) -> HashMap<String, Vec<String>> {
+        if self.mapping.is_empty() {
+            Self::default_mapping()
+        } else {
+            self.mapping.clone()
+        }
+    }
+
+    ///Face buttons on the hand they sit on, the left hand's A/B stand in for X/Y on controllers which lack them
+    pub fn default_mapping() -> HashMap<String, Vec<String>> {
+        [
+            ("/input/a/click", vec!["/user/hand/right/input/a/click"]),
+            ("/input/b/click", vec!["/user/hand/right/input/b/click"]),
+            ("/input/x/click", vec!["/user/hand/left/input/x/click", "/user/hand/left/input/a/click"]),
+            ("/input/y/click", vec!["/user/hand/left/input/y/click", "/user/hand/left/input/b/click"]),
+            ("/input/menu/click", vec!["/user/hand/left/input/menu/click", "/user/hand/right/input/system/click"]),
+            ("/input/view/click", vec!["/user/hand/left/input/system/click"]),
+            ("/input/shoulder_left/click", vec!["/user/hand/left/input/squeeze/value"]),
+            ("/input/shoulder_right/click", vec!["/user/hand/right/input/squeeze/value"]),
+            ("/input/trigger_left", vec!["/user/hand/left/input/trigger"]),
+            ("/input/trigger_right", vec!["/user/hand/right/input/trigger"]),
+            ("/input/thumbstick_left", vec!["/user/hand/left/input/thumbstick"]),
+            ("/input/thumbstick_right", vec!["/user/hand/right/input/thumbstick"]),
+            ("/output/haptic_left", vec!["/user/hand/left/output/haptic"]),
+            ("/output/haptic_right", vec!["/user/hand/right/output/haptic"]),
+        ]
+        .iter()
+        .map(|(gamepad, hands)| {
+            (
+                gamepad.to_string(),
+                hands.iter().map(|hand| hand.to_string()).collect(),
+            )
+        })
+        .collect()
+    }
+}
+
+#[test]
+fn test_json() {
+    let mut settings = ApplicationSettings::default();
+    settings.pose_filters.insert(
+        "/user/hand/left".to_owned(),
+        PoseFilter::OneEuro {
+            min_cutoff: 1.0,
+            beta: 0.5,
+            derivative_cutoff: 1.0,
+        },
+    );
+    settings.pose_filters.insert(
+        "/user/hand/right".to_owned(),
+        PoseFilter::Exponential {
+            time_constant: 0.02,
+        },
+    );
+
+    settings.playspace = Some(PlayspaceOffset {
+        translation: [0.5, 0.0],
+        height: -0.05,
+        yaw: 90.0,
+    });
+
+    settings.space_drag = Some(SpaceDrag {
+        interaction_profile: "/interaction_profiles/valve/index_controller".to_owned(),
+        chord: vec![
+            "/user/hand/left/input/b/click".to_owned(),
+            "/user/hand/left/input/trigger/click".to_owned(),
+        ],
+        hand: "/user/hand/left".to_owned(),
+        mode: SpaceDragMode::Translate,
+    });
+
+    let json = serde_json::to_string_pretty(&settings).unwrap();
+    println!("{}", json);
+
+    let parsed: ApplicationSettings = serde_json::from_str(&json).unwrap();
+    assert_eq!(parsed.pose_filters, settings.pose_filters);
+    assert_eq!(parsed.playspace, settings.playspace);
+    assert_eq!(parsed.space_drag, settings.space_drag);
+
+    let gamepad_emulation: GamepadEmulation = serde_json::from_str("{}").unwrap();
+    assert_eq!(gamepad_emulation.mapping(), GamepadEmulation::default_mapping());
+}
@@ -0,0 +1,28 @@
+use serde::{Deserialize, Serialize};
+
+use crate::serial::CONFIG_DIR;
+
+///Asks the layer to bind the next input the user presses to an action
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct CaptureRequest {
+    pub action_set: String,
+    pub action: String,
+    ///Only inputs of this profile are captured, any profile if not set
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub interaction_profile: Option<String>,
+    ///Replace the action's bindings for the profile instead of adding to them
+    #[serde(skip_serializing_if = "std::ops::Not::not", default)]
+    pub replace: bool,
+    ///Seconds to wait for an input before giving up
+    #[serde(default = "default_timeout")]
+    pub timeout: f32,
+}
+
+fn default_timeout() -> f32 {
+    10.0
+}
+
+///The editor writes a request here, the layer removes it once the capture is over
+pub fn capture_path(uuid: &str) -> String {
+    format!("{}{}/capture.json", CONFIG_DIR, uuid)
+}
@@ -0,0 +1,81 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::application_bindings::PoseOffset;
+use crate::serial::CONFIG_DIR;
+
+///The glTF models for one interaction profile, keyed by top level user path (e.g. /user/hand/left)
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+pub struct ControllerModels {
+    #[serde(flatten)]
+    pub user_paths: HashMap<String, ControllerModel>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct ControllerModel {
+    ///A .glb file, relative to the directory of the json file
+    pub model: String,
+    ///Nodes of the model which move with the controller's inputs
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub nodes: Vec<ModelNode>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct ModelNode {
+    pub node_name: String,
+    ///Left empty if the node name is unique within the model
+    #[serde(skip_serializing_if = "String::is_empty", default)]
+    pub parent_node_name: String,
+    ///The input subpath driving the node (e.g. /input/trigger/value), /x and /y select an axis of 2D inputs
+    pub input: String,
+    ///The node's pose relative to its parent while the input is at 0
+    #[serde(default)]
+    pub rest: PoseOffset,
+    ///The node's pose relative to its parent while the input is at 1, other values are interpolated
+    #[serde(default)]
+    pub pressed: PoseOffset,
+}
+
+///e.g. /interaction_profiles/valve/index_controller -> xrconfig/controller_models/valve/index_controller.json
+pub fn controller_models_path(interaction_profile: &str) -> String {
+    format!(
+        "{}controller_models/{}.json",
+        CONFIG_DIR,
+        interaction_profile.trim_start_matches("/interaction_profiles/")
+    )
+}
+
+#[test]
+fn test_json() {
+    let mut models = ControllerModels::default();
+    models.user_paths.insert(
+        "/user/hand/right".to_owned(),
+        ControllerModel {
+            model: "index_controller_right.glb".to_owned(),
+            nodes: vec![ModelNode {
+                node_name: "trigger".to_owned(),
+                parent_node_name: String::new(),
+                input: "/input/trigger/value".to_owned(),
+                rest: PoseOffset::default(),
+                pressed: PoseOffset {
+                    position: [0.0, 0.0, 0.0],
+                    rotation: [20.0, 0.0, 0.0],
+                },
+            }],
+        },
+    );
+
+    let json = serde_json::to_string_pretty(&models).unwrap();
+    let models: ControllerModels = serde_json::from_str(&json).unwrap();
+    assert_eq!(
+        models.user_paths["/user/hand/right"].nodes[0]
+            .pressed
+            .rotation,
+        [20.0, 0.0, 0.0]
+    );
+    assert_eq!(
+        controller_models_path("/interaction_profiles/valve/index_controller"),
+        "xrconfig/controller_models/valve/index_controller.json"
+    );
+}
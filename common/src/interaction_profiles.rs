@@ -354,6 +354,64 @@ pub fn generate() -> Root {
                     }
                 }
             },
+
+            "/interaction_profiles/htc/vive_tracker_htcx": {
+                "title": "HTC Vive Tracker",
+                "type": "tracked_controller",
+                "monado_device": "XRT_DEVICE_VIVE_TRACKER",
+                "subaction_paths": [
+                    "/user/vive_tracker_htcx/role/handheld_object",
+                    "/user/vive_tracker_htcx/role/left_foot",
+                    "/user/vive_tracker_htcx/role/right_foot",
+                    "/user/vive_tracker_htcx/role/left_shoulder",
+                    "/user/vive_tracker_htcx/role/right_shoulder",
+                    "/user/vive_tracker_htcx/role/left_elbow",
+                    "/user/vive_tracker_htcx/role/right_elbow",
+                    "/user/vive_tracker_htcx/role/left_knee",
+                    "/user/vive_tracker_htcx/role/right_knee",
+                    "/user/vive_tracker_htcx/role/waist",
+                    "/user/vive_tracker_htcx/role/chest",
+                    "/user/vive_tracker_htcx/role/camera",
+                    "/user/vive_tracker_htcx/role/keyboard"
+                ],
+                "subpaths": {
+                    "/input/system": {
+                        "type": "button",
+                        "localized_name": "System",
+                        "features": ["click"]
+                    },
+                    "/input/menu": {
+                        "type": "button",
+                        "localized_name": "Menu",
+                        "features": ["click"]
+                    },
+                    "/input/squeeze": {
+                        "type": "button",
+                        "localized_name": "Squeeze",
+                        "features": ["click"]
+                    },
+                    "/input/trigger": {
+                        "type": "trigger",
+                        "localized_name": "Trigger",
+                        "features": ["click", "value"]
+                    },
+                    "/input/trackpad": {
+                        "type": "trackpad",
+                        "localized_name": "Trackpad",
+                        "features": ["click", "touch", "position"]
+                    },
+                    "/input/grip": {
+                        "type": "pose",
+                        "localized_name": "Grip",
+                        "features": ["pose"]
+                    },
+                    "/output/haptic": {
+                        "type": "vibration",
+                        "localized_name": "Haptic",
+                        "features": ["haptic"]
+                    }
+                }
+            },
     
             "/interaction_profiles/microsoft/motion_controller": {
                 "title": "Microsoft Mixed Reality Motion Controller",
@@ -0,0 +1,223 @@
+//! The protocol spoken between the layer and tools (e.g. the binding editor) over a local socket
+//!
+//! Every message is a single line of JSON. Each process running the layer listens on its own socket,
+//! advertised by an endpoint file in `{CONFIG_DIR}ipc/`. Tools send `Request`s and get exactly one
+//! `Response` back per request, in order, with `Event`s interleaved once they have subscribed.
+
+use std::io::{self, BufRead, Write};
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::application_bindings::ApplicationBindings;
+use crate::application_settings::PlayspaceOffset;
+use crate::capture::CaptureRequest;
+use crate::serial::CONFIG_DIR;
+use crate::xrapplication_info::ActionType;
+
+///Bumped whenever a message changes incompatibly
+pub const PROTOCOL_VERSION: u32 = 1;
+
+pub fn ipc_dir() -> String {
+    format!("{}ipc/", CONFIG_DIR)
+}
+
+pub fn endpoint_path(pid: u32) -> String {
+    format!("{}{}.json", ipc_dir(), pid)
+}
+
+///Written by the layer so tools can find it
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct Endpoint {
+    pub version: u32,
+    pub pid: u32,
+    ///A socket path on unix, a named pipe on windows
+    pub address: String,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct Message<T> {
+    pub version: u32,
+    ///Copied from a request into its response, 0 for events
+    pub id: u64,
+    pub body: T,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(rename_all = "snake_case")]
+pub enum Request {
+    ListInstances,
+    ///The state of every attached action of a session
+    GetActionStates {
+        session: u64,
+    },
+    ///The state of every input of the runtime, optionally limited to one interaction profile
+    GetGodStates {
+        session: u64,
+        #[serde(skip_serializing_if = "Option::is_none", default)]
+        interaction_profile: Option<String>,
+    },
+    ///Replaces the user's bindings for the instance's application
+    SetBindings {
+        instance: u64,
+        bindings: ApplicationBindings,
+    },
+    Capture {
+        session: u64,
+        request: CaptureRequest,
+    },
+    CancelCapture {
+        session: u64,
+    },
+    GetPlayspace {
+        session: u64,
+    },
+    ///Moves the session's STAGE and LOCAL spaces, saving the offset to the application's settings if asked to
+    SetPlayspace {
+        session: u64,
+        playspace: PlayspaceOffset,
+        #[serde(default)]
+        save: bool,
+    },
+    Subscribe,
+    ///Sends a snapshot of the session after every sync until the connection is closed
+    Inspect { session: u64 },
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(rename_all = "snake_case")]
+pub enum Response {
+    Ok,
+    Error(String),
+    Instances(Vec<InstanceInfo>),
+    ActionStates(Vec<ActionStateInfo>),
+    GodStates(Vec<GodStateInfo>),
+    Playspace(PlayspaceOffset),
+    Event(Event),
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(rename_all = "snake_case")]
+pub enum Event {
+    BindingsChanged {
+        session: u64,
+    },
+    ///The binding is None if nothing was pressed before the timeout or the capture failed
+    CaptureFinished {
+        session: u64,
+        #[serde(skip_serializing_if = "Option::is_none", default)]
+        binding: Option<String>,
+        ///Why the captured input couldn't be saved to the user's bindings
+        #[serde(skip_serializing_if = "Option::is_none", default)]
+        error: Option<String>,
+    },
+    Snapshot(SessionSnapshot),
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct InstanceInfo {
+    pub handle: u64,
+    pub application_name: String,
+    pub engine_name: String,
+    pub sessions: Vec<SessionInfo>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct SessionInfo {
+    pub handle: u64,
+    ///Top level user path to interaction profile, empty if nothing is connected
+    pub active_profiles: Vec<(String, String)>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct ActionStateInfo {
+    pub action_set: String,
+    pub action: String,
+    pub action_type: ActionType,
+    pub state: StateValue,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct GodStateInfo {
+    pub interaction_profile: String,
+    pub binding: String,
+    pub state: StateValue,
+}
+
+///Everything needed to see why an action has the state it has
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct SessionSnapshot {
+    pub session: u64,
+    ///Counts the application's xrSyncActions calls
+    pub sync_idx: u64,
+    pub active_profiles: Vec<(String, String)>,
+    pub actions: Vec<ActionSnapshot>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct ActionSnapshot {
+    pub state: ActionStateInfo,
+    ///The state the application gets for each of the action's subaction paths
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub subaction_states: Vec<(String, StateValue)>,
+    ///The inputs feeding the action and their raw states, paired with the subaction path they feed
+    pub bindings: Vec<(Option<String>, GodStateInfo)>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum StateValue {
+    Boolean { is_active: bool, value: bool },
+    Float { is_active: bool, value: f32 },
+    Vector2f { is_active: bool, value: [f32; 2] },
+    Pose { is_active: bool },
+}
+
+pub fn write_message<T: Serialize, W: Write>(writer: &mut W, id: u64, body: &T) -> io::Result<()> {
+    let message = Message {
+        version: PROTOCOL_VERSION,
+        id,
+        body,
+    };
+    let mut line = serde_json::to_string(&message)?;
+    line.push('\n');
+    writer.write_all(line.as_bytes())?;
+    writer.flush()
+}
+
+///Ok(None) once the other end has closed the connection
+pub fn read_message<T: DeserializeOwned, R: BufRead>(
+    reader: &mut R,
+) -> io::Result<Option<Message<T>>> {
+    let mut line = String::new();
+    if reader.read_line(&mut line)? == 0 {
+        return Ok(None);
+    }
+    Ok(Some(serde_json::from_str(&line)?))
+}
+
+#[test]
+fn test_round_trip() {
+    let mut buffer = Vec::new();
+    write_message(
+        &mut buffer,
+        7,
+        &Request::GetGodStates {
+            session: 1,
+            interaction_profile: Some("/interaction_profiles/valve/index_controller".to_owned()),
+        },
+    )
+    .unwrap();
+    write_message(&mut buffer, 8, &Request::Subscribe).unwrap();
+
+    let mut reader = io::Cursor::new(buffer);
+    let message = read_message::<Request, _>(&mut reader).unwrap().unwrap();
+    assert_eq!(message.version, PROTOCOL_VERSION);
+    assert_eq!(message.id, 7);
+    assert!(matches!(
+        message.body,
+        Request::GetGodStates { session: 1, .. }
+    ));
+    let message = read_message::<Request, _>(&mut reader).unwrap().unwrap();
+    assert!(matches!(message.body, Request::Subscribe));
+    assert!(read_message::<Request, _>(&mut reader).unwrap().is_none());
+}
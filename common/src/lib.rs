@@ -1,4 +1,8 @@
 pub mod serial;
 pub mod xrapplication_info;
 pub mod application_bindings;
-pub mod interaction_profiles;
\ No newline at end of file
+pub mod application_settings;
+pub mod interaction_profiles;
+pub mod controller_models;
+pub mod capture;
+pub mod ipc;
\ No newline at end of file
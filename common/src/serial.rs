@@ -52,6 +52,23 @@ pub fn read_json<T>(path_str: &str) -> Option<T> where T: DeserializeOwned {
     }
 }
 
+///Like read_json but returns None instead of panicking if the file can't be read or parsed
+pub fn try_read_json<T>(path_str: &str) -> Option<T> where T: DeserializeOwned {
+    let path = Path::new(&path_str);
+
+    let file = match fs::read_to_string(&path) {
+        Err(_) => return None,
+        Ok(file) => file,
+    };
+    match serde_json::from_str(&file) {
+        Err(why) => {
+            println!("couldn't parse {}: {}", path.display(), why);
+            None
+        },
+        Ok(value) => Some(value),
+    }
+}
+
 pub fn write_json<T>(value: &T, path: &Path) where T: Serialize {
     // let display = path.display();
 
@@ -67,4 +84,27 @@ pub fn write_json<T>(value: &T, path: &Path) where T: Serialize {
     //     },
     //     Err(why) => panic!("couldn't write to {}: {}", display, why),
     // }
-}
\ No newline at end of file
+}
+///Like write_json but reports failure instead of panicking, used by the layer while the application runs
+pub fn try_write_json<T>(value: &T, path: &Path) -> bool where T: Serialize {
+    if let Some(parent) = path.parent() {
+        if let Err(why) = fs::create_dir_all(parent) {
+            println!("couldn't create directory {}: {}", parent.display(), why);
+            return false;
+        }
+    }
+
+    match serde_json::to_string_pretty(&value) {
+        Ok(json) => match fs::write(path, &json) {
+            Ok(_) => true,
+            Err(why) => {
+                println!("couldn't write to {}: {}", path.display(), why);
+                false
+            },
+        },
+        Err(why) => {
+            println!("couldn't serialize value {}: {}", path.display(), why);
+            false
+        },
+    }
+}
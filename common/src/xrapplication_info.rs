@@ -75,6 +75,14 @@ impl ActionType {
         }
     }
 
+    ///Whether an input component of type `source` can be bound directly to an action of this type
+    pub fn accepts(&self, source: ActionType) -> bool {
+        match self {
+            ActionType::BooleanInput | ActionType::FloatInput => source.is_primitive(),
+            _ => *self == source,
+        }
+    }
+
     pub fn is_input(&self) -> bool {
         match self {
             ActionType::VibrationOutput | ActionType::Unknown => false,
@@ -0,0 +1,152 @@
+//! Prints a live table of an application's actions and the inputs feeding them
+//!
+//! Usage: inspect [pid] [session]
+//! Without a pid the first running application found is inspected.
+
+use std::env;
+use std::io::{self, BufReader, Write};
+
+use common::ipc::*;
+
+#[cfg(windows)]
+use std::fs::File as Stream;
+#[cfg(unix)]
+use std::os::unix::net::UnixStream as Stream;
+
+pub fn main() {
+    let args: Vec<String> = env::args().collect();
+
+    let endpoint = match args.get(1) {
+        Some(pid) => common::serial::read_json::<Endpoint>(&endpoint_path(
+            pid.parse().expect("pid must be a number"),
+        )),
+        None => find_endpoint(),
+    }
+    .expect("no application with the layer is running");
+
+    let stream = connect(&endpoint.address).unwrap();
+    let mut reader = BufReader::new(stream.try_clone().unwrap());
+    let mut writer = stream;
+
+    write_message(&mut writer, 1, &Request::ListInstances).unwrap();
+    let instances = match read_message::<Response, _>(&mut reader)
+        .unwrap()
+        .unwrap()
+        .body
+    {
+        Response::Instances(instances) => instances,
+        response => panic!("unexpected response {:?}", response),
+    };
+
+    let session = match args.get(2) {
+        Some(session) => session.parse().expect("session must be a number"),
+        None => {
+            instances
+                .iter()
+                .flat_map(|instance| instance.sessions.iter())
+                .next()
+                .expect("the application has no session")
+                .handle
+        }
+    };
+    for instance in &instances {
+        println!("{} ({})", instance.application_name, instance.engine_name);
+    }
+
+    write_message(&mut writer, 2, &Request::Inspect { session }).unwrap();
+    while let Some(message) = read_message::<Response, _>(&mut reader).unwrap() {
+        match message.body {
+            Response::Event(Event::Snapshot(snapshot)) => print_snapshot(&snapshot).unwrap(),
+            Response::Error(why) => panic!("{}", why),
+            _ => (),
+        }
+    }
+}
+
+#[cfg(unix)]
+fn connect(address: &str) -> io::Result<Stream> {
+    Stream::connect(address)
+}
+
+///The layer listens on a named pipe
+#[cfg(windows)]
+fn connect(address: &str) -> io::Result<Stream> {
+    std::fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open(address)
+}
+
+///The endpoint of any process which is still listening
+fn find_endpoint() -> Option<Endpoint> {
+    std::fs::read_dir(ipc_dir())
+        .ok()?
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| {
+            entry
+                .path()
+                .extension()
+                .map_or(false, |extension| extension == "json")
+        })
+        .filter_map(|entry| common::serial::try_read_json::<Endpoint>(entry.path().to_str()?))
+        .find(|endpoint| endpoint.version == PROTOCOL_VERSION && connect(&endpoint.address).is_ok())
+}
+
+fn print_snapshot(snapshot: &SessionSnapshot) -> io::Result<()> {
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+
+    //Clear the terminal and go back to the top left
+    write!(out, "\x1b[2J\x1b[H")?;
+    writeln!(
+        out,
+        "Session {} sync {}",
+        snapshot.session, snapshot.sync_idx
+    )?;
+    for (user_path, profile) in &snapshot.active_profiles {
+        writeln!(out, "  {:<24} {}", user_path, profile)?;
+    }
+
+    for action in &snapshot.actions {
+        writeln!(out)?;
+        writeln!(
+            out,
+            "{}/{} {:<28} {}",
+            action.state.action_set,
+            action.state.action,
+            format!("{:?}", action.state.action_type),
+            format_state(&action.state.state)
+        )?;
+        for (subaction_path, state) in &action.subaction_states {
+            writeln!(out, "  {:<56} {}", subaction_path, format_state(state))?;
+        }
+        for (subaction_path, binding) in &action.bindings {
+            writeln!(
+                out,
+                "    <- {:<53} {}{}",
+                binding.binding,
+                format_state(&binding.state),
+                subaction_path
+                    .as_ref()
+                    .map_or(String::new(), |path| format!(" -> {}", path))
+            )?;
+        }
+    }
+    out.flush()
+}
+
+fn format_state(state: &StateValue) -> String {
+    let (is_active, value) = match state {
+        StateValue::Boolean { is_active, value } => (is_active, value.to_string()),
+        StateValue::Float { is_active, value } => (is_active, format!("{:.3}", value)),
+        StateValue::Vector2f { is_active, value } => {
+            (is_active, format!("({:.3}, {:.3})", value[0], value[1]))
+        }
+        StateValue::Pose { is_active } => (is_active, String::new()),
+    };
+    if *is_active {
+        value
+    } else {
+        format!("inactive {}", value)
+    }
+}
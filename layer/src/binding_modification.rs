@@ -0,0 +1,263 @@
+use std::collections::HashMap;
+use std::os::raw::c_void;
+
+use common::application_bindings::{
+    AnalogThreshold, BindingModifiers, HapticTrigger, TriggerCondition,
+};
+use openxr::sys as xr;
+
+use crate::wrappers::*;
+
+pub const EXTENSION_NAME: &'static str = "XR_KHR_binding_modification";
+pub const ANALOG_THRESHOLD_EXTENSION_NAME: &'static str = "XR_VALVE_analog_threshold";
+
+///XR_TYPE_BINDING_MODIFICATIONS_KHR, newer than the headers we build against
+pub const TYPE_BINDING_MODIFICATIONS: xr::StructureType = xr::StructureType::from_raw(1000120000);
+
+///XrBindingModificationsKHR
+#[repr(C)]
+pub struct BindingModifications {
+    pub ty: xr::StructureType,
+    pub next: *const c_void,
+    pub binding_modification_count: u32,
+    pub binding_modifications: *const *const xr::BaseInStructure,
+}
+
+///The modifiers for each (action, binding) pair of a single xrSuggestInteractionProfileBindings call
+pub type SuggestedModifiers = HashMap<(xr::Action, xr::Path), BindingModifiers>;
+
+type Handler = unsafe fn(*const xr::BaseInStructure, &mut SuggestedModifiers);
+
+///The binding modifications the layer understands and the extension the application must enable to use each
+const HANDLERS: &[(xr::StructureType, &'static str, Handler)] = &[(
+    xr::StructureType::INTERACTION_PROFILE_ANALOG_THRESHOLD_VALVE,
+    ANALOG_THRESHOLD_EXTENSION_NAME,
+    analog_threshold,
+)];
+
+///Collects every modification in the next chain of the suggested bindings
+///The layer never forwards the application's suggestions (see god_actions::god_suggestion) so the runtime doesn't need them stripped
+pub unsafe fn parse(
+    instance: &InstanceWrapper,
+    suggested_bindings: &xr::InteractionProfileSuggestedBinding,
+) -> SuggestedModifiers {
+    parse_chain(
+        &|extension_name| instance.layer_extension_enabled(extension_name),
+        suggested_bindings,
+    )
+}
+
+unsafe fn parse_chain(
+    extension_enabled: &dyn Fn(&str) -> bool,
+    suggested_bindings: &xr::InteractionProfileSuggestedBinding,
+) -> SuggestedModifiers {
+    let mut modifiers = HashMap::new();
+
+    let mut next = suggested_bindings.next as *const xr::BaseInStructure;
+    while !next.is_null() {
+        if (*next).ty == TYPE_BINDING_MODIFICATIONS {
+            if extension_enabled(EXTENSION_NAME) {
+                let binding_modifications = &*(next as *const BindingModifications);
+                for i in 0..binding_modifications.binding_modification_count as usize {
+                    dispatch(
+                        extension_enabled,
+                        *binding_modifications.binding_modifications.add(i),
+                        &mut modifiers,
+                    );
+                }
+            }
+        } else {
+            //Older applications chain modifications directly onto the suggested bindings
+            dispatch(extension_enabled, next, &mut modifiers);
+        }
+        next = (*next).next;
+    }
+
+    modifiers
+}
+
+unsafe fn dispatch(
+    extension_enabled: &dyn Fn(&str) -> bool,
+    modification: *const xr::BaseInStructure,
+    modifiers: &mut SuggestedModifiers,
+) {
+    if modification.is_null() {
+        return;
+    }
+    match HANDLERS.iter().find(|(ty, _, _)| *ty == (*modification).ty) {
+        Some((_, extension_name, handler)) if extension_enabled(extension_name) => {
+            handler(modification, modifiers)
+        }
+        _ => println!("Ignoring binding modification {:?}", (*modification).ty),
+    }
+}
+
+unsafe fn analog_threshold(
+    modification: *const xr::BaseInStructure,
+    modifiers: &mut SuggestedModifiers,
+) {
+    let analog_threshold = &*(modification as *const xr::InteractionProfileAnalogThresholdVALVE);
+    let modifiers = modifiers
+        .entry((analog_threshold.action, analog_threshold.binding))
+        .or_default();
+
+    modifiers.analog_threshold = Some(AnalogThreshold {
+        on_threshold: analog_threshold.on_threshold,
+        off_threshold: analog_threshold.off_threshold,
+    });
+
+    for (haptic, condition) in [
+        (analog_threshold.on_haptic, TriggerCondition::Press),
+        (analog_threshold.off_haptic, TriggerCondition::Release),
+    ] {
+        if let Some(haptic_trigger) = haptic_trigger(haptic, condition) {
+            modifiers.haptic_triggers.push(haptic_trigger);
+        }
+    }
+}
+
+unsafe fn haptic_trigger(
+    haptic: *const xr::HapticBaseHeader,
+    condition: TriggerCondition,
+) -> Option<HapticTrigger> {
+    if haptic.is_null() || (*haptic).ty != xr::StructureType::HAPTIC_VIBRATION {
+        return None;
+    }
+    let vibration = &*(haptic as *const xr::HapticVibration);
+    Some(HapticTrigger {
+        condition,
+        output: None,
+        //XR_MIN_HAPTIC_DURATION is negative
+        duration: vibration.duration.as_nanos().max(0) as f32 / 1e9,
+        amplitude: vibration.amplitude,
+        frequency: vibration.frequency,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ptr;
+
+    fn suggested_bindings(next: *const c_void) -> xr::InteractionProfileSuggestedBinding {
+        xr::InteractionProfileSuggestedBinding {
+            ty: xr::StructureType::INTERACTION_PROFILE_SUGGESTED_BINDING,
+            next,
+            interaction_profile: xr::Path::from_raw(1),
+            count_suggested_bindings: 0,
+            suggested_bindings: ptr::null(),
+        }
+    }
+
+    fn vibration(duration: i64) -> xr::HapticVibration {
+        xr::HapticVibration {
+            ty: xr::StructureType::HAPTIC_VIBRATION,
+            next: ptr::null(),
+            duration: xr::Duration::from_nanos(duration),
+            frequency: 160f32,
+            amplitude: 0.5,
+        }
+    }
+
+    fn threshold(
+        action: u64,
+        on_haptic: *const xr::HapticVibration,
+        off_haptic: *const xr::HapticVibration,
+    ) -> xr::InteractionProfileAnalogThresholdVALVE {
+        xr::InteractionProfileAnalogThresholdVALVE {
+            ty: xr::StructureType::INTERACTION_PROFILE_ANALOG_THRESHOLD_VALVE,
+            next: ptr::null(),
+            action: xr::Action::from_raw(action),
+            binding: xr::Path::from_raw(2),
+            on_threshold: 0.6,
+            off_threshold: 0.4,
+            on_haptic: on_haptic as *const xr::HapticBaseHeader,
+            off_haptic: off_haptic as *const xr::HapticBaseHeader,
+        }
+    }
+
+    fn modifiers(modifiers: &SuggestedModifiers, action: u64) -> Option<&BindingModifiers> {
+        modifiers.get(&(xr::Action::from_raw(action), xr::Path::from_raw(2)))
+    }
+
+    #[test]
+    fn analog_threshold_in_binding_modifications() {
+        let on_haptic = vibration(100_000_000);
+        let analog_threshold = threshold(1, &on_haptic, ptr::null());
+        let modification_ptrs = [
+            &analog_threshold as *const _ as *const xr::BaseInStructure,
+            ptr::null(),
+        ];
+        let binding_modifications = BindingModifications {
+            ty: TYPE_BINDING_MODIFICATIONS,
+            next: ptr::null(),
+            binding_modification_count: modification_ptrs.len() as u32,
+            binding_modifications: modification_ptrs.as_ptr(),
+        };
+        let bindings = suggested_bindings(&binding_modifications as *const _ as *const c_void);
+
+        let all_enabled = |_: &str| true;
+        let parsed = unsafe { parse_chain(&all_enabled, &bindings) };
+        assert_eq!(parsed.len(), 1);
+        let parsed = modifiers(&parsed, 1).unwrap();
+        assert_eq!(
+            parsed.analog_threshold,
+            Some(AnalogThreshold {
+                on_threshold: 0.6,
+                off_threshold: 0.4,
+            })
+        );
+        assert_eq!(parsed.haptic_triggers.len(), 1);
+        let haptic_trigger = &parsed.haptic_triggers[0];
+        assert_eq!(haptic_trigger.condition, TriggerCondition::Press);
+        assert_eq!(haptic_trigger.duration, 0.1);
+        assert_eq!(haptic_trigger.amplitude, 0.5);
+        assert_eq!(haptic_trigger.frequency, 160f32);
+    }
+
+    #[test]
+    fn analog_threshold_chained_directly() {
+        let off_haptic = vibration(xr::Duration::MIN_HAPTIC.as_nanos());
+        let analog_threshold = threshold(1, ptr::null(), &off_haptic);
+        let bindings = suggested_bindings(&analog_threshold as *const _ as *const c_void);
+
+        let only_threshold = |name: &str| name == ANALOG_THRESHOLD_EXTENSION_NAME;
+        let parsed = unsafe { parse_chain(&only_threshold, &bindings) };
+        let parsed = modifiers(&parsed, 1).unwrap();
+        assert_eq!(parsed.haptic_triggers.len(), 1);
+        assert_eq!(
+            parsed.haptic_triggers[0].condition,
+            TriggerCondition::Release
+        );
+        //XR_MIN_HAPTIC_DURATION becomes the shortest pulse the runtime allows
+        assert_eq!(parsed.haptic_triggers[0].duration, 0f32);
+    }
+
+    #[test]
+    fn disabled_extensions_are_ignored() {
+        let first = threshold(1, ptr::null(), ptr::null());
+        let mut second = threshold(2, ptr::null(), ptr::null());
+        second.next = &first as *const _ as *const c_void;
+        let modification_ptrs = [&first as *const _ as *const xr::BaseInStructure];
+        let binding_modifications = BindingModifications {
+            ty: TYPE_BINDING_MODIFICATIONS,
+            next: &second as *const _ as *const c_void,
+            binding_modification_count: modification_ptrs.len() as u32,
+            binding_modifications: modification_ptrs.as_ptr(),
+        };
+        let bindings = suggested_bindings(&binding_modifications as *const _ as *const c_void);
+
+        let none_enabled = |_: &str| false;
+        assert!(unsafe { parse_chain(&none_enabled, &bindings) }.is_empty());
+
+        //Without XR_KHR_binding_modification only the directly chained structs are read
+        let only_threshold = |name: &str| name == ANALOG_THRESHOLD_EXTENSION_NAME;
+        let parsed = unsafe { parse_chain(&only_threshold, &bindings) };
+        assert_eq!(parsed.len(), 2);
+        assert!(modifiers(&parsed, 1).is_some());
+        assert!(modifiers(&parsed, 2).is_some());
+
+        let only_binding_modification = |name: &str| name == EXTENSION_NAME;
+        assert!(unsafe { parse_chain(&only_binding_modification, &bindings) }.is_empty());
+    }
+}
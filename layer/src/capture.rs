@@ -0,0 +1,244 @@
+use std::collections::HashSet;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use common::application_bindings::{ActionBindings, ApplicationBindings};
+use common::capture::CaptureRequest;
+use common::serial::{try_read_json, try_write_json};
+use common::xrapplication_info::ActionType;
+use openxr::sys as xr;
+
+use crate::god_actions::{GodActionStateEnum, InputBinding};
+use crate::wrappers::*;
+
+///How far an analog input has to move before it counts as pressed
+const CAPTURE_THRESHOLD: f32 = 0.75;
+
+pub struct CaptureState {
+    pub request: CaptureRequest,
+    pub action: Arc<ActionWrapper>,
+    pub action_type: ActionType,
+    pub armed_at: Instant,
+    ///Whether the request was written to the capture file rather than sent over IPC
+    pub from_file: bool,
+    ///Inputs which were already held when the capture started, they have to be released first
+    pub held: Option<HashSet<xr::Path>>,
+}
+
+pub enum CaptureResult {
+    Captured(String),
+    TimedOut,
+    ///An input was pressed but the user's bindings couldn't be updated
+    Failed(String),
+}
+
+///Starts waiting for the next input to bind to an action
+pub fn arm(
+    session: &SessionWrapper,
+    request: CaptureRequest,
+    from_file: bool,
+) -> Result<(), String> {
+    let instance = session.instance();
+
+    let action = instance
+        .action_sets
+        .read()
+        .unwrap()
+        .iter()
+        .filter(|action_set| action_set.name == request.action_set)
+        .flat_map(|action_set| action_set.actions.read().unwrap().clone())
+        .find(|action| action.name == request.action)
+        .ok_or_else(|| format!("no action {}/{}", request.action_set, request.action))?;
+    let action_type = action.action_type;
+
+    //Poses can't be pressed
+    if !action_type.is_input() || action_type == ActionType::PoseInput {
+        return Err(format!("{:?} actions can not be captured", action_type));
+    }
+
+    println!(
+        "Capturing input for {}/{}",
+        request.action_set, request.action
+    );
+    *session.capture.write().unwrap() = Some(CaptureState {
+        request,
+        action,
+        action_type,
+        armed_at: Instant::now(),
+        from_file,
+        held: None,
+    });
+    Ok(())
+}
+
+pub fn cancel(session: &SessionWrapper) {
+    *session.capture.write().unwrap() = None;
+}
+
+///Cancels the capture only if it was requested through the capture file
+pub fn cancel_from_file(session: &SessionWrapper) {
+    let mut capture = session.capture.write().unwrap();
+    if capture.as_ref().map_or(false, |state| state.from_file) {
+        *capture = None;
+    }
+}
+
+pub fn is_armed(session: &SessionWrapper) -> bool {
+    session.capture.read().unwrap().is_some()
+}
+
+pub fn is_from_file(session: &SessionWrapper) -> bool {
+    session
+        .capture
+        .read()
+        .unwrap()
+        .as_ref()
+        .map_or(false, |state| state.from_file)
+}
+
+fn is_pressed(state: &GodActionStateEnum) -> bool {
+    match state {
+        GodActionStateEnum::Boolean(state) => state.is_active && state.current_state,
+        GodActionStateEnum::Float(state) => {
+            state.is_active && state.current_state.abs() > CAPTURE_THRESHOLD
+        }
+        GodActionStateEnum::Vector2f(state) => {
+            state.is_active
+                && (state.current_state.x.powi(2) + state.current_state.y.powi(2)).sqrt()
+                    > CAPTURE_THRESHOLD
+        }
+        GodActionStateEnum::Pose(_) => false,
+    }
+}
+
+///Looks for a newly pressed input once every god state has been synced, writing it to the user's bindings
+pub fn update(session: &SessionWrapper, bindings_path: &str) -> Option<CaptureResult> {
+    let mut capture = session.capture.write().unwrap();
+    let state = capture.as_mut()?;
+
+    if state.armed_at.elapsed() > Duration::from_secs_f32(state.request.timeout) {
+        println!("Capture timed out");
+        *capture = None;
+        return Some(CaptureResult::TimedOut);
+    }
+
+    let instance = session.instance();
+    let profile = match &state.request.interaction_profile {
+        Some(profile) => instance.string_to_path(profile).ok(),
+        None => None,
+    };
+
+    let candidates = session
+        .god_states
+        .iter()
+        .filter(|(god_profile, _)| profile.map_or(true, |profile| profile == **god_profile))
+        .flat_map(|(_, god_states)| god_states.iter())
+        .filter(|(_, god_state)| state.action_type.accepts(god_state.action.action_type))
+        .filter(|(_, god_state)| is_pressed(&god_state.action_state.read().unwrap()))
+        .collect::<Vec<_>>();
+
+    //The first sync only records what is already held
+    if state.held.is_none() {
+        state.held = Some(candidates.iter().map(|(binding, _)| **binding).collect());
+        return None;
+    }
+    let held = state.held.as_mut().unwrap();
+    held.retain(|binding| {
+        candidates
+            .iter()
+            .any(|(candidate, _)| *candidate == binding)
+    });
+
+    //Pressing a trigger fires both its click and value, prefer the one matching the action
+    //Anything still tied is picked by path so the same press always captures the same binding
+    let captured: &Arc<InputBinding> = candidates
+        .iter()
+        .filter(|(binding, _)| !held.contains(*binding))
+        .map(|(_, god_state)| *god_state)
+        .min_by(|a, b| {
+            let matches =
+                |god_state: &InputBinding| god_state.action.action_type == state.action_type;
+            matches(b)
+                .cmp(&matches(a))
+                .then_with(|| a.action.profile_name_str.cmp(&b.action.profile_name_str))
+                .then_with(|| a.binding_str.cmp(&b.binding_str))
+        })?;
+
+    let binding_str = captured.binding_str.clone();
+    let profile_name_str = captured.action.profile_name_str.clone();
+    let profile_name = captured.action.profile_name;
+    //The capture ends here whether or not the bindings can be saved
+    let CaptureState {
+        request, action, ..
+    } = capture.take().unwrap();
+
+    let mut application_bindings = if Path::new(bindings_path).exists() {
+        match try_read_json::<ApplicationBindings>(bindings_path) {
+            Some(application_bindings) => application_bindings,
+            None => {
+                println!("Capture failed, couldn't read {}", bindings_path);
+                return Some(CaptureResult::Failed(format!(
+                    "couldn't read {}",
+                    bindings_path
+                )));
+            }
+        }
+    } else {
+        ApplicationBindings::default()
+    };
+    let action_bindings = application_bindings
+        .profiles
+        .entry(profile_name_str)
+        .or_default()
+        .action_sets
+        .entry(request.action_set)
+        .or_default()
+        .actions
+        .entry(request.action)
+        //A user entry replaces the application's suggestions for the action, so start from those
+        .or_insert_with(|| suggested_action_bindings(&instance, &action, profile_name));
+    if request.replace {
+        action_bindings.bindings.clear();
+    }
+    if !action_bindings.bindings.contains(&binding_str) {
+        action_bindings.bindings.push(binding_str.clone());
+    }
+
+    if try_write_json(&application_bindings, Path::new(bindings_path)) {
+        println!("Captured {}", binding_str);
+        Some(CaptureResult::Captured(binding_str))
+    } else {
+        println!("Capture failed, couldn't write {}", bindings_path);
+        Some(CaptureResult::Failed(format!(
+            "couldn't write {}",
+            bindings_path
+        )))
+    }
+}
+
+fn suggested_action_bindings(
+    instance: &InstanceWrapper,
+    action: &ActionWrapper,
+    profile: xr::Path,
+) -> ActionBindings {
+    ActionBindings {
+        bindings: action
+            .suggested_bindings
+            .read()
+            .unwrap()
+            .get(&profile)
+            .into_iter()
+            .flatten()
+            .filter_map(|binding| instance.path_to_string(*binding).ok())
+            .collect(),
+        modifiers: action
+            .suggested_modifiers
+            .read()
+            .unwrap()
+            .get(&profile)
+            .cloned()
+            .unwrap_or_default(),
+        ..Default::default()
+    }
+}
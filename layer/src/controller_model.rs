@@ -0,0 +1,302 @@
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use common::application_bindings::PoseOffset;
+use common::controller_models::{controller_models_path, ControllerModels, ModelNode};
+use common::serial::try_read_json;
+use openxr::sys as xr;
+
+use crate::god_actions::{GodActionStateEnum, InputBinding};
+use crate::math;
+use crate::path::*;
+use crate::util::place_cstr;
+use crate::wrappers::*;
+
+pub const EXTENSION_NAME: &'static str = "XR_MSFT_controller_model";
+
+///A model handed out to the application, its key is its index in the session's list plus one
+pub struct LoadedControllerModel {
+    pub user_path: xr::Path,
+    pub interaction_profile: xr::Path,
+    pub model_path: PathBuf,
+    pub nodes: Vec<ModelNode>,
+    ///Holding these keeps the god states synced even when the application has nothing bound to them
+    pub node_states: Vec<Option<Arc<InputBinding>>>,
+}
+
+impl LoadedControllerModel {
+    fn load(
+        session: &SessionWrapper,
+        user_path: xr::Path,
+        interaction_profile: xr::Path,
+    ) -> Option<Self> {
+        let instance = session.instance();
+        let user_path_str = instance.path_to_string(user_path).ok()?;
+        let profile_str = instance.path_to_string(interaction_profile).ok()?;
+
+        let json_path = controller_models_path(&profile_str);
+        let mut models: ControllerModels = try_read_json(&json_path)?;
+        let model = models.user_paths.remove(&user_path_str)?;
+
+        let god_states = session.god_states.get(&interaction_profile);
+        let node_states = model
+            .nodes
+            .iter()
+            .map(|node| {
+                let god_states = god_states?;
+                //Axes of 2D inputs are read from the input itself
+                let input = node
+                    .input
+                    .strip_suffix("/x")
+                    .or(node.input.strip_suffix("/y"))
+                    .unwrap_or(&node.input);
+                let binding = instance
+                    .string_to_path(&format!("{}{}", user_path_str, input))
+                    .ok()?;
+                god_states.get(&binding).cloned()
+            })
+            .collect();
+
+        Some(LoadedControllerModel {
+            user_path,
+            interaction_profile,
+            model_path: Path::new(&json_path).with_file_name(&model.model),
+            nodes: model.nodes,
+            node_states,
+        })
+    }
+
+    fn node_pose(node: &ModelNode, state: Option<&Arc<InputBinding>>) -> xr::Posef {
+        let value = match state {
+            Some(state) => match &*state.action_state.read().unwrap() {
+                GodActionStateEnum::Boolean(state) => state.current_state as u8 as f32,
+                GodActionStateEnum::Float(state) => state.current_state,
+                GodActionStateEnum::Vector2f(state) => {
+                    if node.input.ends_with("/y") {
+                        state.current_state.y
+                    } else {
+                        state.current_state.x
+                    }
+                }
+                GodActionStateEnum::Pose(_) => 0.0,
+            },
+            None => 0.0,
+        };
+
+        let lerp = |a: [f32; 3], b: [f32; 3]| {
+            [
+                a[0] + (b[0] - a[0]) * value,
+                a[1] + (b[1] - a[1]) * value,
+                a[2] + (b[2] - a[2]) * value,
+            ]
+        };
+        offset_pose(&PoseOffset {
+            position: lerp(node.rest.position, node.pressed.position),
+            rotation: lerp(node.rest.rotation, node.pressed.rotation),
+        })
+    }
+}
+
+fn offset_pose(offset: &PoseOffset) -> xr::Posef {
+    xr::Posef {
+        orientation: math::quat_from_euler_degrees(offset.rotation),
+        position: xr::Vector3f {
+            x: offset.position[0],
+            y: offset.position[1],
+            z: offset.position[2],
+        },
+    }
+}
+
+fn get_model<'a>(
+    models: &'a [LoadedControllerModel],
+    model_key: xr::ControllerModelKeyMSFT,
+) -> Option<&'a LoadedControllerModel> {
+    let idx = model_key.into_raw().checked_sub(1)?;
+    models.get(idx as usize)
+}
+
+pub unsafe extern "system" fn get_controller_model_key(
+    session: xr::Session,
+    top_level_user_path: xr::Path,
+    controller_model_key_state: *mut xr::ControllerModelKeyStateMSFT,
+) -> xr::Result {
+    let session = match session.get_wrapper() {
+        Some(session) => session,
+        None => return xr::Result::ERROR_HANDLE_INVALID,
+    };
+
+    let interaction_profile = match session
+        .active_profiles
+        .get(&TopLevelUserPath(top_level_user_path))
+    {
+        Some(active_profile) => active_profile.read().unwrap().0,
+        None => return xr::Result::ERROR_PATH_UNSUPPORTED,
+    };
+
+    //No model is available until a device is connected
+    (*controller_model_key_state).model_key = xr::ControllerModelKeyMSFT::NULL;
+    if interaction_profile == xr::Path::NULL {
+        return xr::Result::SUCCESS;
+    }
+
+    let device = (top_level_user_path, interaction_profile);
+    if session
+        .missing_controller_models
+        .read()
+        .unwrap()
+        .contains(&device)
+    {
+        return xr::Result::SUCCESS;
+    }
+    let find = |models: &[LoadedControllerModel]| {
+        models.iter().position(|model| {
+            model.user_path == top_level_user_path
+                && model.interaction_profile == interaction_profile
+        })
+    };
+
+    let loaded = find(&session.controller_models.read().unwrap());
+    let idx = match loaded {
+        Some(idx) => idx,
+        None => {
+            let mut models = session.controller_models.write().unwrap();
+            //Another thread may have loaded it while the lock was released
+            match find(&models) {
+                Some(idx) => idx,
+                None => match LoadedControllerModel::load(
+                    &session,
+                    top_level_user_path,
+                    interaction_profile,
+                ) {
+                    Some(model) => {
+                        models.push(model);
+                        models.len() - 1
+                    }
+                    None => {
+                        session
+                            .missing_controller_models
+                            .write()
+                            .unwrap()
+                            .insert(device);
+                        return xr::Result::SUCCESS;
+                    }
+                },
+            }
+        }
+    };
+
+    (*controller_model_key_state).model_key = xr::ControllerModelKeyMSFT::from_raw(idx as u64 + 1);
+    xr::Result::SUCCESS
+}
+
+pub unsafe extern "system" fn load_controller_model(
+    session: xr::Session,
+    model_key: xr::ControllerModelKeyMSFT,
+    buffer_capacity_input: u32,
+    buffer_count_output: *mut u32,
+    buffer: *mut u8,
+) -> xr::Result {
+    let session = match session.get_wrapper() {
+        Some(session) => session,
+        None => return xr::Result::ERROR_HANDLE_INVALID,
+    };
+    let models = session.controller_models.read().unwrap();
+    let model = match get_model(&models, model_key) {
+        Some(model) => model,
+        None => return xr::Result::ERROR_CONTROLLER_MODEL_KEY_INVALID_MSFT,
+    };
+
+    let bytes = match std::fs::read(&model.model_path) {
+        Ok(bytes) => bytes,
+        Err(err) => {
+            println!(
+                "Failed to read controller model {:?}: {}",
+                model.model_path, err
+            );
+            return xr::Result::ERROR_RUNTIME_FAILURE;
+        }
+    };
+
+    *buffer_count_output = bytes.len() as u32;
+    if buffer_capacity_input == 0 {
+        return xr::Result::SUCCESS;
+    }
+    if (buffer_capacity_input as usize) < bytes.len() {
+        return xr::Result::ERROR_SIZE_INSUFFICIENT;
+    }
+    std::ptr::copy_nonoverlapping(bytes.as_ptr(), buffer, bytes.len());
+
+    xr::Result::SUCCESS
+}
+
+pub unsafe extern "system" fn get_controller_model_properties(
+    session: xr::Session,
+    model_key: xr::ControllerModelKeyMSFT,
+    properties: *mut xr::ControllerModelPropertiesMSFT,
+) -> xr::Result {
+    let session = match session.get_wrapper() {
+        Some(session) => session,
+        None => return xr::Result::ERROR_HANDLE_INVALID,
+    };
+    let models = session.controller_models.read().unwrap();
+    let model = match get_model(&models, model_key) {
+        Some(model) => model,
+        None => return xr::Result::ERROR_CONTROLLER_MODEL_KEY_INVALID_MSFT,
+    };
+    let properties = &mut *properties;
+
+    properties.node_count_output = model.nodes.len() as u32;
+    if properties.node_capacity_input == 0 {
+        return xr::Result::SUCCESS;
+    }
+    if (properties.node_capacity_input as usize) < model.nodes.len() {
+        return xr::Result::ERROR_SIZE_INSUFFICIENT;
+    }
+
+    for (i, node) in model.nodes.iter().enumerate() {
+        if node.node_name.len() >= xr::MAX_CONTROLLER_MODEL_NODE_NAME_SIZE_MSFT
+            || node.parent_node_name.len() >= xr::MAX_CONTROLLER_MODEL_NODE_NAME_SIZE_MSFT
+        {
+            println!("Controller model node name too long: {}", node.node_name);
+            return xr::Result::ERROR_RUNTIME_FAILURE;
+        }
+        let out = &mut *properties.node_properties.add(i);
+        place_cstr(&mut out.parent_node_name, &node.parent_node_name);
+        place_cstr(&mut out.node_name, &node.node_name);
+    }
+
+    xr::Result::SUCCESS
+}
+
+pub unsafe extern "system" fn get_controller_model_state(
+    session: xr::Session,
+    model_key: xr::ControllerModelKeyMSFT,
+    state: *mut xr::ControllerModelStateMSFT,
+) -> xr::Result {
+    let session = match session.get_wrapper() {
+        Some(session) => session,
+        None => return xr::Result::ERROR_HANDLE_INVALID,
+    };
+    let models = session.controller_models.read().unwrap();
+    let model = match get_model(&models, model_key) {
+        Some(model) => model,
+        None => return xr::Result::ERROR_CONTROLLER_MODEL_KEY_INVALID_MSFT,
+    };
+    let state = &mut *state;
+
+    state.node_count_output = model.nodes.len() as u32;
+    if state.node_capacity_input == 0 {
+        return xr::Result::SUCCESS;
+    }
+    if (state.node_capacity_input as usize) < model.nodes.len() {
+        return xr::Result::ERROR_SIZE_INSUFFICIENT;
+    }
+
+    for (i, (node, node_state)) in model.nodes.iter().zip(&model.node_states).enumerate() {
+        (*state.node_states.add(i)).node_pose =
+            LoadedControllerModel::node_pose(node, node_state.as_ref());
+    }
+
+    xr::Result::SUCCESS
+}
@@ -0,0 +1,199 @@
+use std::os::raw::c_char;
+use std::sync::RwLock;
+
+use openxr::sys as xr;
+use openxr::sys::pfn;
+
+use crate::binding_modification;
+use crate::controller_model;
+use crate::eye_gaze;
+use crate::local_floor;
+use crate::oxidexr_ext;
+use crate::palm_pose;
+use crate::util::*;
+
+///An extension the layer implements on top of the runtime
+pub struct LayerExtension {
+    pub name: &'static str,
+    pub version: u32,
+    ///The layer implements the extension even when the runtime supports it
+    pub overrides_runtime: bool,
+}
+
+pub const LAYER_EXTENSIONS: &[LayerExtension] = &[
+    LayerExtension {
+        name: local_floor::EXTENSION_NAME,
+        version: 1,
+        overrides_runtime: false,
+    },
+    LayerExtension {
+        name: palm_pose::EXTENSION_NAME,
+        version: 1,
+        overrides_runtime: true,
+    },
+    LayerExtension {
+        name: eye_gaze::EXTENSION_NAME,
+        version: 1,
+        overrides_runtime: false,
+    },
+    //The application's suggested bindings never reach the runtime so the layer has to handle their modifications
+    LayerExtension {
+        name: binding_modification::EXTENSION_NAME,
+        version: 1,
+        overrides_runtime: true,
+    },
+    LayerExtension {
+        name: binding_modification::ANALOG_THRESHOLD_EXTENSION_NAME,
+        version: 2,
+        overrides_runtime: true,
+    },
+    LayerExtension {
+        name: controller_model::EXTENSION_NAME,
+        version: 2,
+        overrides_runtime: false,
+    },
+    LayerExtension {
+        name: oxidexr_ext::EXTENSION_NAME,
+        version: 1,
+        overrides_runtime: true,
+    },
+];
+
+///xrGetInstanceProcAddr of the layer bellow us, only known once an instance has been created
+pub static NEXT_GET_INSTANCE_PROC_ADDR: RwLock<Option<pfn::GetInstanceProcAddr>> =
+    RwLock::new(None);
+
+pub fn set_get_instance_proc_addr_next(get_instance_proc_addr_next: pfn::GetInstanceProcAddr) {
+    *NEXT_GET_INSTANCE_PROC_ADDR.write().unwrap() = Some(get_instance_proc_addr_next);
+}
+
+///The extensions the layer has to implement given what the application enabled and what the runtime supports
+pub fn layer_implemented(
+    app_extensions: &[String],
+    runtime_extensions: &[String],
+) -> Vec<&'static LayerExtension> {
+    LAYER_EXTENSIONS
+        .iter()
+        .filter(|extension| app_extensions.iter().any(|name| name == extension.name))
+        .filter(|extension| {
+            extension.overrides_runtime
+                || !runtime_extensions.iter().any(|name| name == extension.name)
+        })
+        .collect()
+}
+
+///xrEnumerateInstanceExtensionProperties of the layer bellow us
+unsafe fn enumerate_function_next(
+    get_instance_proc_addr_next: pfn::GetInstanceProcAddr,
+) -> openxr::Result<pfn::EnumerateInstanceExtensionProperties> {
+    let mut function = None;
+    check(get_instance_proc_addr_next(
+        xr::Instance::NULL,
+        "xrEnumerateInstanceExtensionProperties\0".as_ptr() as *const c_char,
+        &mut function,
+    ))?;
+    Ok(std::mem::transmute(function.unwrap()))
+}
+
+///The extensions supported by the layers bellow us and the runtime
+pub unsafe fn enumerate_extensions_next(
+    get_instance_proc_addr_next: pfn::GetInstanceProcAddr,
+) -> openxr::Result<Vec<xr::ExtensionProperties>> {
+    let enumerate_instance_extension_properties =
+        enumerate_function_next(get_instance_proc_addr_next)?;
+
+    let mut count = 0;
+    check(enumerate_instance_extension_properties(
+        std::ptr::null(),
+        0,
+        &mut count,
+        std::ptr::null_mut(),
+    ))?;
+
+    let mut properties = vec![extension_properties("", 0); count as usize];
+    check(enumerate_instance_extension_properties(
+        std::ptr::null(),
+        count,
+        &mut count,
+        properties.as_mut_ptr(),
+    ))?;
+    properties.truncate(count as usize);
+
+    Ok(properties)
+}
+
+pub fn extension_properties(name: &str, version: u32) -> xr::ExtensionProperties {
+    let mut extension_name = [0; xr::MAX_EXTENSION_NAME_SIZE];
+    place_cstr(&mut extension_name, name);
+    xr::ExtensionProperties {
+        ty: xr::ExtensionProperties::TYPE,
+        next: std::ptr::null_mut(),
+        extension_name,
+        extension_version: version,
+    }
+}
+
+pub unsafe extern "system" fn enumerate_instance_extension_properties(
+    layer_name: *const c_char,
+    property_capacity_input: u32,
+    property_count_output: *mut u32,
+    properties: *mut xr::ExtensionProperties,
+) -> xr::Result {
+    let own_layer = !layer_name.is_null()
+        && std::ffi::CStr::from_ptr(layer_name).to_bytes() == LAYER_NAME.as_bytes();
+    //Nothing bellow us is known until an instance has been created, then only our own extensions can be listed
+    let get_instance_proc_addr_next = *NEXT_GET_INSTANCE_PROC_ADDR.read().unwrap();
+
+    //Another layer's extensions are none of our business
+    if !layer_name.is_null() && !own_layer {
+        let get_instance_proc_addr_next = match get_instance_proc_addr_next {
+            Some(get_instance_proc_addr_next) => get_instance_proc_addr_next,
+            None => return xr::Result::ERROR_API_LAYER_NOT_PRESENT,
+        };
+        return match enumerate_function_next(get_instance_proc_addr_next) {
+            Ok(enumerate_instance_extension_properties) => enumerate_instance_extension_properties(
+                layer_name,
+                property_capacity_input,
+                property_count_output,
+                properties,
+            ),
+            Err(result) => result,
+        };
+    }
+
+    //Asking for the runtime, merge ours into the list of whatever is bellow us
+    let mut merged = Vec::new();
+    if !own_layer {
+        if let Some(get_instance_proc_addr_next) = get_instance_proc_addr_next {
+            merged = match enumerate_extensions_next(get_instance_proc_addr_next) {
+                Ok(properties) => properties,
+                Err(result) => return result,
+            };
+        }
+    }
+
+    for extension in LAYER_EXTENSIONS {
+        if !merged
+            .iter()
+            .any(|properties| i8_arr_to_owned(&properties.extension_name) == extension.name)
+        {
+            merged.push(extension_properties(extension.name, extension.version));
+        }
+    }
+
+    *property_count_output = merged.len() as u32;
+    if property_capacity_input == 0 {
+        return xr::Result::SUCCESS;
+    }
+    if (property_capacity_input as usize) < merged.len() {
+        return xr::Result::ERROR_SIZE_INSUFFICIENT;
+    }
+
+    for (i, extension) in merged.iter().enumerate() {
+        let out = &mut *properties.add(i);
+        out.extension_name = extension.extension_name;
+        out.extension_version = extension.extension_version;
+    }
+
+    xr::Result::SUCCESS
+}
@@ -0,0 +1,7 @@
+pub const EXTENSION_NAME: &'static str = "XR_EXT_eye_gaze_interaction";
+
+pub const PROFILE: &'static str = "/interaction_profiles/ext/eye_gaze_interaction";
+
+pub const USER_PATH: &'static str = "/user/eyes_ext";
+
+pub const GAZE_POSE: &'static str = "/input/gaze_ext/pose";
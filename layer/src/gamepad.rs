@@ -0,0 +1,122 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use openxr::sys as xr;
+
+use crate::injections::instance::queue_event;
+use crate::path::*;
+use crate::wrappers::*;
+
+pub const PROFILE: &'static str = "/interaction_profiles/microsoft/xbox_controller";
+
+pub const USER_PATH: &'static str = "/user/gamepad";
+
+pub fn is_enabled(instance: &InstanceWrapper) -> bool {
+    instance
+        .settings
+        .read()
+        .unwrap()
+        .gamepad_emulation
+        .is_some()
+}
+
+///The hand bindings of every other profile which feed a binding of the emulated gamepad
+pub fn emulated_bindings<T>(
+    instance: &InstanceWrapper,
+    gamepad_binding: &str,
+    profile_map: &HashMap<xr::Path, HashMap<xr::Path, Arc<T>>>,
+) -> Vec<Arc<T>> {
+    let mapping = match &instance.settings.read().unwrap().gamepad_emulation {
+        Some(gamepad_emulation) => gamepad_emulation.mapping(),
+        None => return Vec::new(),
+    };
+    let subpath = match gamepad_binding.strip_prefix(USER_PATH) {
+        Some(subpath) => subpath,
+        None => return Vec::new(),
+    };
+    let gamepad_profile = match instance.string_to_path(PROFILE) {
+        Ok(gamepad_profile) => gamepad_profile,
+        Err(_) => return Vec::new(),
+    };
+
+    //A mapping for a whole input (e.g. /input/thumbstick_left) also covers its features (e.g. /input/thumbstick_left/x)
+    let mut targets = Vec::new();
+    for (from, hands) in &mapping {
+        let feature = if subpath == from {
+            ""
+        } else if subpath.starts_with(from.as_str()) && subpath[from.len()..].starts_with('/') {
+            &subpath[from.len()..]
+        } else {
+            continue;
+        };
+        for hand in hands {
+            targets.push(format!("{}{}", hand, feature));
+        }
+    }
+
+    let mut bindings = Vec::new();
+    for target in targets {
+        let target = match instance.string_to_path(&target) {
+            Ok(target) => target,
+            Err(_) => continue,
+        };
+        for (profile, profile_bindings) in profile_map {
+            if *profile == gamepad_profile {
+                continue;
+            }
+            if let Some(binding) = profile_bindings.get(&target) {
+                bindings.push(binding.clone());
+            }
+        }
+    }
+    bindings
+}
+
+///Shows the gamepad as connected while the runtime has none and a hand is tracked
+///The application is told about the emulated gamepad appearing and going away like any other profile change
+pub fn update_active_profile(session: &SessionWrapper) {
+    let instance = session.instance();
+    let emulating = is_enabled(&instance) && emulate(&instance, &session.active_profiles);
+
+    let mut was_emulating = session.emulating_gamepad.write().unwrap();
+    if emulating != *was_emulating {
+        *was_emulating = emulating;
+        queue_event(
+            &instance,
+            &xr::EventDataInteractionProfileChanged {
+                ty: xr::EventDataInteractionProfileChanged::TYPE,
+                next: std::ptr::null(),
+                session: session.handle,
+            },
+        );
+    }
+}
+
+///Sets the gamepad's profile if it should be emulated, returning whether it is
+fn emulate(
+    instance: &InstanceWrapper,
+    active_profiles: &HashMap<TopLevelUserPath, std::sync::RwLock<InteractionProfilePath>>,
+) -> bool {
+    let (gamepad, gamepad_profile) = match (
+        instance.string_to_path(USER_PATH),
+        instance.string_to_path(PROFILE),
+    ) {
+        (Ok(gamepad), Ok(gamepad_profile)) => (gamepad, gamepad_profile),
+        _ => return false,
+    };
+
+    let hands_active = [openxr::USER_HAND_LEFT, openxr::USER_HAND_RIGHT]
+        .iter()
+        .filter_map(|hand| instance.string_to_path(hand).ok())
+        .filter_map(|hand| active_profiles.get(&TopLevelUserPath(hand)))
+        .any(|profile| profile.read().unwrap().0 != xr::Path::NULL);
+
+    if let Some(active_profile) = active_profiles.get(&TopLevelUserPath(gamepad)) {
+        let mut active_profile = active_profile.write().unwrap();
+        if active_profile.0 == xr::Path::NULL && hands_active {
+            *active_profile = InteractionProfilePath(gamepad_profile);
+            return true;
+        }
+    }
+    false
+}
@@ -1,7 +1,19 @@
+use common::application_bindings::ActionBindings;
+use common::application_bindings::Axis;
+use common::application_bindings::AxisSign;
+use common::application_bindings::BindingModifiers;
+use common::application_bindings::ConflictPolicy;
+use common::application_bindings::Decomposition;
+use common::application_bindings::Ramp;
+use common::application_bindings::TriggerCondition;
 use common::interaction_profiles;
 use common::interaction_profiles::InteractionProfile;
 use common::interaction_profiles::Subpath;
 use common::xrapplication_info::ActionType;
+use crate::eye_gaze;
+use crate::gamepad;
+use crate::vive_tracker;
+use crate::palm_pose;
 use crate::path::*;
 
 use openxr::Result;
@@ -27,11 +39,21 @@ pub fn create_god_action_sets(
 ) -> Result<HashMap<xr::Path, GodActionSet>> {
     let mut map = HashMap::new();
     for (profile_name, profile_info) in interaction_profiles::generate().profiles {
+        //The role paths are only valid user paths with the extension enabled
+        if profile_name == vive_tracker::PROFILE && instance.enumerate_vive_tracker_paths.is_none() {
+            continue;
+        }
         map.insert(
             instance.string_to_path(&profile_name)?,
             GodActionSet::create_set(instance, &profile_name, &profile_info)?,
         );
     }
+    if instance.layer_extension_enabled(eye_gaze::EXTENSION_NAME) {
+        map.insert(
+            instance.string_to_path(eye_gaze::PROFILE)?,
+            GodActionSet::create_eye_gaze_set(instance)?,
+        );
+    }
     Ok(map)
 }
 
@@ -80,9 +102,33 @@ impl GodActionSet {
             god_set.create_actions_for_subpath(instance, &subpath, &subpath_info)?;
         }
 
+        //XR_EXT_palm_pose is emulated on top of the grip pose
+        if instance.layer_extension_enabled(palm_pose::EXTENSION_NAME) && !profile_info.subpaths.contains_key(palm_pose::SUBPATH) {
+            if let Some(grip) = god_set
+                .god_actions
+                .get(&instance.string_to_path("/input/grip/pose")?)
+                .cloned()
+            {
+                let name = format!("{}/pose", palm_pose::SUBPATH);
+                god_set.god_actions.insert(
+                    instance.string_to_path(&name)?,
+                    Arc::new(GodAction {
+                        handle: grip.handle,
+                        profile_name_str: grip.profile_name_str.clone(),
+                        profile_name: grip.profile_name,
+                        name,
+                        subaction_paths: grip.subaction_paths.clone(),
+                        action_type: ActionType::PoseInput,
+                        emulated: Some(EmulatedSource::PalmPose),
+                    }),
+                );
+            }
+        }
+
         let mut bindings = Vec::new();
 
-        for god_action in god_set.god_actions.values() {
+        //Emulated actions share their handle with a real one so the runtime never sees their paths
+        for god_action in god_set.god_actions.values().filter(|god_action| god_action.emulated.is_none()) {
             for subaction_path in &god_action.subaction_paths {
                 let name = instance.path_to_string(*subaction_path)?.add(&god_action.name);
                 bindings.push(xr::ActionSuggestedBinding {
@@ -92,13 +138,7 @@ impl GodActionSet {
             }
         }
 
-        let suggested_bindings = xr::InteractionProfileSuggestedBinding {
-            ty: xr::InteractionProfileSuggestedBinding::TYPE,
-            next: ptr::null(),
-            interaction_profile: instance.string_to_path(&profile_name)?,
-            count_suggested_bindings: bindings.len() as u32,
-            suggested_bindings: bindings.as_ptr(),
-        };
+        let suggested_bindings = god_suggestion(instance.string_to_path(&profile_name)?, &bindings);
 
         //TODO deal with some system components not existing causing XR_ERROR_PATH_UNSUPPORTED
         let result = instance.suggest_interaction_profile_bindings(&suggested_bindings);
@@ -112,6 +152,35 @@ impl GodActionSet {
         Ok(god_set)
     }
 
+    ///The emulated eye gaze profile has no runtime action set, its single pose is driven by the VIEW space
+    fn create_eye_gaze_set(instance: &InstanceWrapper) -> Result<Self> {
+        let profile_name = eye_gaze::PROFILE.to_owned();
+        let name = eye_gaze::GAZE_POSE.to_owned();
+
+        let mut god_actions = HashMap::new();
+        god_actions.insert(
+            instance.string_to_path(&name)?,
+            Arc::new(GodAction {
+                handle: xr::Action::NULL,
+                profile_name_str: profile_name.clone(),
+                profile_name: instance.string_to_path(&profile_name)?,
+                name,
+                subaction_paths: vec![instance.string_to_path(eye_gaze::USER_PATH)?],
+                action_type: ActionType::PoseInput,
+                emulated: Some(EmulatedSource::HeadGaze),
+            }),
+        );
+
+        println!("Created God Set: Eye Gaze Interaction (emulated), {}", &profile_name);
+
+        Ok(GodActionSet {
+            handle: xr::ActionSet::NULL,
+            subaction_paths: vec![eye_gaze::USER_PATH.to_owned()],
+            god_actions,
+            name: profile_name,
+        })
+    }
+
     fn create_actions_for_subpath(
         &mut self,
         instance: &InstanceWrapper,
@@ -219,6 +288,7 @@ impl GodActionSet {
                 name,
                 subaction_paths,
                 action_type,
+                emulated: None,
             }),
         );
 
@@ -233,6 +303,16 @@ pub struct GodAction {
     pub name: String,
     pub subaction_paths: Vec<xr::Path>,
     pub action_type: ActionType,
+    ///Set for inputs the layer provides itself
+    pub emulated: Option<EmulatedSource>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EmulatedSource {
+    ///A palm_ext pose which shares the handle of the grip pose
+    PalmPose,
+    ///An eye gaze pose which follows the VIEW space, its god action has no handle
+    HeadGaze,
 }
 
 pub struct InputBinding {
@@ -277,13 +357,185 @@ pub enum CachedActionStatesEnum {
 pub struct CachedActionStates<T: OxideActionState> {
     pub main_state: T,
     pub subaction_states: Option<HashMap<xr::Path, T>>,
+    pub user_bindings: UserBindings,
+    pub modifier_states: ModifierStates,
+}
+
+///The user's bindings for an action in each profile they have edited
+pub type UserBindings = HashMap<xr::Path /* interactionProfile */, ActionBindings>;
+
+///What the modifiers of each of an action's bindings remember between syncs
+pub type ModifierStates = HashMap<xr::Path /* interactionProfile */, HashMap<String /* binding */, ModifierState>>;
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ModifierState {
+    ///The value the binding's ramp has reached
+    pub ramp: f32,
+    pub ramp_change_time: xr::Time,
+    ///Whether the binding's analog threshold is pressed
+    pub threshold_on: bool,
+    ///Whether the threshold was crossed by the latest sync
+    pub threshold_changed: bool,
+    pub threshold_change_time: xr::Time,
+}
+
+impl ModifierState {
+    fn step(mut self, modifiers: &BindingModifiers, state: &GodActionStateEnum, time: xr::Time, delta: f32) -> Self {
+        if !state.get_inner().is_active() {
+            return Self::default();
+        }
+
+        if let (Some(ramp), GodActionStateEnum::Boolean(state)) = (modifiers.ramp, state) {
+            let target = if state.current_state { 1f32 } else { 0f32 };
+            let value = apply_ramp(&ramp, self.ramp, target, delta);
+            //The ramp changes on the sync which moves it, so its change time never goes back
+            if value != self.ramp {
+                self.ramp = value;
+                self.ramp_change_time = time;
+            }
+        }
+
+        if let Some(threshold) = modifiers.analog_threshold {
+            self.threshold_changed = false;
+            let value = match (state, &modifiers.decomposition) {
+                (GodActionStateEnum::Vector2f(state), Some(decomposition)) => {
+                    Some(decompose(decomposition, state.current_state))
+                }
+                (state, _) => state.get_inner().get_scalar().ok(),
+            };
+            if let Some(value) = value {
+                let threshold_on = threshold.is_on(self.threshold_on, value);
+                if threshold_on != self.threshold_on {
+                    self.threshold_on = threshold_on;
+                    self.threshold_changed = true;
+                    self.threshold_change_time = time;
+                }
+            }
+        }
+
+        self
+    }
+}
+
+///The only bindings the layer suggests to the runtime
+///Nothing from the application's suggestions is forwarded, so binding modifications the layer implements never reach the runtime
+fn god_suggestion(
+    interaction_profile: xr::Path,
+    bindings: &[xr::ActionSuggestedBinding],
+) -> xr::InteractionProfileSuggestedBinding {
+    xr::InteractionProfileSuggestedBinding {
+        ty: xr::InteractionProfileSuggestedBinding::TYPE,
+        next: ptr::null(),
+        interaction_profile,
+        count_suggested_bindings: bindings.len() as u32,
+        suggested_bindings: bindings.as_ptr(),
+    }
+}
+
+///The pressed state haptic triggers follow, analog bindings with a threshold are pressed while it is
+pub fn get_pressed_state(
+    state: &GodActionStateEnum,
+    modifiers: &BindingModifiers,
+    modifier_state: Option<&ModifierState>,
+) -> Option<openxr::ActionState<bool>> {
+    match (state, modifiers.analog_threshold, modifier_state) {
+        (state, _, _) if !state.get_inner().is_active() => None,
+        (_, Some(_), Some(modifier_state)) => Some(openxr::ActionState::<bool> {
+            current_state: modifier_state.threshold_on,
+            changed_since_last_sync: modifier_state.threshold_changed,
+            last_change_time: modifier_state.threshold_change_time,
+            is_active: true,
+        }),
+        (GodActionStateEnum::Boolean(state), _, _) => Some(*state),
+        _ => None,
+    }
+}
+
+///Whether a haptic trigger plays on the sync at time, delta seconds after the previous one
+pub fn is_triggered(condition: TriggerCondition, state: &openxr::ActionState<bool>, time: xr::Time, delta: f32) -> bool {
+    match condition {
+        TriggerCondition::Press => state.changed_since_last_sync && state.current_state,
+        TriggerCondition::Release => state.changed_since_last_sync && !state.current_state,
+        TriggerCondition::Change => state.changed_since_last_sync,
+        TriggerCondition::LongPress { seconds } => {
+            //Only play on the sync where the hold time crosses the threshold
+            let threshold = state.last_change_time.as_nanos() + (seconds as f64 * 1e9) as i64;
+            let previous_time = time.as_nanos() - (delta as f64 * 1e9) as i64;
+            state.current_state && previous_time < threshold && threshold <= time.as_nanos()
+        }
+    }
+}
+
+///Everything an action state needs to know to sync from its bindings
+pub struct SyncContext<'a> {
+    ///The time of the sync, estimated from the runtime's clock
+    pub time: xr::Time,
+    ///Seconds since the previous sync
+    pub delta: f32,
+    pub user_bindings: &'a UserBindings,
+    pub modifier_states: &'a ModifierStates,
+    ///How the states of the action's bindings are combined
+    pub conflict_policy: ConflictPolicy,
+    ///Whether the device of a binding is the current one on its user path or blended with it
+    pub is_device_active: &'a dyn Fn(InteractionProfilePath, TopLevelUserPath) -> bool,
+}
+
+impl<'a> SyncContext<'a> {
+    ///Whether a binding can contribute to the action, it also has to be active itself
+    pub fn is_binding_active(&self, binding: &InputBinding, state: &GodActionStateEnum) -> bool {
+        state.get_inner().is_active()
+            && (self.is_device_active)(
+                InteractionProfilePath(binding.action.profile_name),
+                TopLevelUserPath(binding.subaction_path),
+            )
+    }
+
+    pub fn get_modifiers(&self, binding: &InputBinding) -> Option<&'a BindingModifiers> {
+        self.user_bindings
+            .get(&binding.action.profile_name)
+            .and_then(|action_bindings| action_bindings.modifiers.get(&binding.binding_str))
+    }
+
+    pub fn get_modifier_state(&self, binding: &InputBinding) -> Option<&'a ModifierState> {
+        self.modifier_states
+            .get(&binding.action.profile_name)
+            .and_then(|states| states.get(&binding.binding_str))
+    }
+
+    ///The value and last change time of a binding's ramp if the user has given it one
+    pub fn get_ramped(&self, binding: &InputBinding) -> Option<(f32, xr::Time)> {
+        self.get_modifiers(binding)?.ramp?;
+        self.get_modifier_state(binding)
+            .map(|state| (state.ramp, state.ramp_change_time))
+    }
+
+    ///Whether a binding's analog threshold is pressed if the user has given it one
+    pub fn get_thresholded(&self, binding: &InputBinding) -> Option<bool> {
+        self.get_modifiers(binding)?.analog_threshold?;
+        self.get_modifier_state(binding).map(|state| state.threshold_on)
+    }
+
+    ///Reduces the state of a 2D binding to a single value if the user has given it a decomposition
+    pub fn get_decomposed(&self, binding: &InputBinding, state: &GodActionStateEnum) -> Option<f32> {
+        match (
+            state,
+            self.get_modifiers(binding)
+                .and_then(|modifiers| modifiers.decomposition.as_ref()),
+        ) {
+            (GodActionStateEnum::Vector2f(state), Some(decomposition)) => {
+                Some(decompose(decomposition, state.current_state))
+            }
+            _ => None,
+        }
+    }
 }
 pub enum SubactionBindings<T>
 where
     T: Binding,
 {
     Singleton(Vec<Arc<T>>),
-    Subactions(HashMap<xr::Path, Vec<Arc<T>>>),
+    ///The bindings of each subaction path, then every binding in the order the user listed them
+    Subactions(HashMap<xr::Path, Vec<Arc<T>>>, Vec<Arc<T>>),
 }
 
 #[derive(Copy, Clone)]
@@ -301,13 +553,28 @@ impl<T: Binding> SubactionBindings<T> {
         profile_map: &HashMap<xr::Path, HashMap<xr::Path, Arc<T>>>,
     ) -> Self {
         let subaction_paths = &action.subaction_paths;
+        let gamepad_profile = if gamepad::is_enabled(instance) {
+            instance.string_to_path(gamepad::PROFILE).ok()
+        } else {
+            None
+        };
+        //The priority policy depends on the order of the bindings, each profile's are kept in the order the user listed them
+        //The bindings file doesn't keep the order of its profiles so they are ordered by name, which unlike their paths is the same every run
+        let action_bindings = action.bindings.read().unwrap();
+        let mut profiles = action_bindings.iter().collect::<Vec<_>>();
+        profiles.sort_by_key(|(profile, _)| instance.path_to_string(**profile).unwrap_or_default());
+
         if subaction_paths.is_empty() {
             let mut vec = Vec::new();
 
-            for (profile, bindings) in action.bindings.read().unwrap().iter() {
+            for &(profile, bindings) in &profiles {
                 let bindings_map = profile_map.get(profile).unwrap();
                 for binding in bindings {
                     vec.push(bindings_map.get(binding).unwrap().clone());
+                    if Some(*profile) == gamepad_profile {
+                        let binding_str = instance.path_to_string(*binding).unwrap();
+                        vec.extend(gamepad::emulated_bindings(instance, &binding_str, profile_map));
+                    }
                 }
             }
 
@@ -317,26 +584,37 @@ impl<T: Binding> SubactionBindings<T> {
                 .iter()
                 .map(|subaction_path| (*subaction_path, Vec::new()))
                 .collect::<HashMap<_, _>>();
+            let mut all = Vec::new();
 
-            for (profile, bindings) in action.bindings.read().unwrap().iter() {
+            let subaction_path_strs = subaction_paths
+                .iter()
+                .map(|subaction_path| (*subaction_path, instance.path_to_string(*subaction_path).unwrap()))
+                .collect::<Vec<_>>();
+            let binding_routes = action.binding_routes.read().unwrap();
+            for &(profile, bindings) in &profiles {
                 let bindings_map = profile_map.get(profile).unwrap();
                 for binding in bindings {
                     let binding_str = instance.path_to_string(*binding).unwrap();
-                    let subaction_path = subaction_paths
-                        .iter()
-                        .filter(|subaction_path| {
-                            binding_str
-                                .starts_with(&instance.path_to_string(**subaction_path).unwrap())
-                        })
-                        .next()
-                        .unwrap();
-                    let vec = map.get_mut(subaction_path).unwrap();
+                    let route = binding_routes.get(&(*profile, *binding)).copied();
+                    let subaction_path = match route_binding(&binding_str, route, &subaction_path_strs) {
+                        Some(subaction_path) => subaction_path,
+                        None => {
+                            println!("Ignoring {} which matches no subaction path of {}", binding_str, action.name);
+                            continue;
+                        }
+                    };
+                    let vec = map.get_mut(&subaction_path).unwrap();
                     println!("{}", binding_str);
-                    vec.push(bindings_map.get(binding).unwrap().clone());
+                    let mut bindings = vec![bindings_map.get(binding).unwrap().clone()];
+                    if Some(*profile) == gamepad_profile {
+                        bindings.extend(gamepad::emulated_bindings(instance, &binding_str, profile_map));
+                    }
+                    vec.extend(bindings.iter().cloned());
+                    all.extend(bindings);
                 }
             }
 
-            SubactionBindings::Subactions(map)
+            SubactionBindings::Subactions(map, all)
         }
     }
 
@@ -344,14 +622,13 @@ impl<T: Binding> SubactionBindings<T> {
         if subaction_path == xr::Path::NULL {
             Ok(match self {
                 SubactionBindings::Singleton(state) => state.iter().collect(),
-                SubactionBindings::Subactions(state_map) => {
-                    state_map.values().flat_map(|v| v.iter()).collect()
-                }
+                //The priority policy depends on the order of the bindings so it can't be left to the map
+                SubactionBindings::Subactions(_, all) => all.iter().collect(),
             })
         } else {
             match self {
                 SubactionBindings::Singleton(_) => Err(xr::Result::ERROR_PATH_UNSUPPORTED),
-                SubactionBindings::Subactions(state_map) => match state_map.get(&subaction_path) {
+                SubactionBindings::Subactions(state_map, _) => match state_map.get(&subaction_path) {
                     Some(state) => Ok(state.iter().collect()),
                     None => Err(xr::Result::ERROR_PATH_UNSUPPORTED),
                 },
@@ -361,7 +638,11 @@ impl<T: Binding> SubactionBindings<T> {
 }
 
 impl CachedActionStatesEnum {
-    pub fn new(action_type: ActionType, subaction_paths: &Vec<xr::Path>) -> Self {
+    pub fn new(
+        action_type: ActionType,
+        subaction_paths: &Vec<xr::Path>,
+        user_bindings: UserBindings,
+    ) -> Self {
         match action_type {
             ActionType::BooleanInput => CachedActionStatesEnum::Boolean(CachedActionStates::new(
                 openxr::ActionState::<bool> {
@@ -371,6 +652,7 @@ impl CachedActionStatesEnum {
                     is_active: false,
                 },
                 subaction_paths,
+                user_bindings,
             )),
             ActionType::FloatInput => CachedActionStatesEnum::Float(CachedActionStates::new(
                 openxr::ActionState::<f32> {
@@ -380,6 +662,7 @@ impl CachedActionStatesEnum {
                     is_active: false,
                 },
                 subaction_paths,
+                user_bindings,
             )),
             ActionType::Vector2fInput => CachedActionStatesEnum::Vector2f(CachedActionStates::new(
                 openxr::ActionState::<openxr::Vector2f> {
@@ -389,28 +672,63 @@ impl CachedActionStatesEnum {
                     is_active: false,
                 },
                 subaction_paths,
+                user_bindings,
             )),
             ActionType::PoseInput => CachedActionStatesEnum::Pose(CachedActionStates::new(
                 ActionStatePose { is_active: false },
                 subaction_paths,
+                user_bindings,
             )),
             _ => panic!(),
         }
     }
 
-    pub fn sync(&mut self, subaction_bindings: &SubactionBindings<InputBinding>) -> Result<()> {
+    pub fn user_bindings(&self) -> &UserBindings {
+        match self {
+            CachedActionStatesEnum::Boolean(states) => &states.user_bindings,
+            CachedActionStatesEnum::Float(states) => &states.user_bindings,
+            CachedActionStatesEnum::Vector2f(states) => &states.user_bindings,
+            CachedActionStatesEnum::Pose(states) => &states.user_bindings,
+        }
+    }
+
+    pub fn modifier_states(&self) -> &ModifierStates {
+        match self {
+            CachedActionStatesEnum::Boolean(states) => &states.modifier_states,
+            CachedActionStatesEnum::Float(states) => &states.modifier_states,
+            CachedActionStatesEnum::Vector2f(states) => &states.modifier_states,
+            CachedActionStatesEnum::Pose(states) => &states.modifier_states,
+        }
+    }
+
+    pub fn set_user_bindings(&mut self, user_bindings: UserBindings) {
+        match self {
+            CachedActionStatesEnum::Boolean(states) => states.user_bindings = user_bindings,
+            CachedActionStatesEnum::Float(states) => states.user_bindings = user_bindings,
+            CachedActionStatesEnum::Vector2f(states) => states.user_bindings = user_bindings,
+            CachedActionStatesEnum::Pose(states) => states.user_bindings = user_bindings,
+        }
+    }
+
+    pub fn sync(
+        &mut self,
+        subaction_bindings: &SubactionBindings<InputBinding>,
+        time: xr::Time,
+        delta: f32,
+        is_device_active: &dyn Fn(InteractionProfilePath, TopLevelUserPath) -> bool,
+    ) -> Result<()> {
         match self as &mut CachedActionStatesEnum {
             CachedActionStatesEnum::Boolean(states) => {
-                states.update_from_bindings(subaction_bindings);
+                states.update_from_bindings(subaction_bindings, time, delta, is_device_active);
             }
             CachedActionStatesEnum::Float(states) => {
-                states.update_from_bindings(subaction_bindings);
+                states.update_from_bindings(subaction_bindings, time, delta, is_device_active);
             }
             CachedActionStatesEnum::Vector2f(states) => {
-                states.update_from_bindings(subaction_bindings);
+                states.update_from_bindings(subaction_bindings, time, delta, is_device_active);
             }
             CachedActionStatesEnum::Pose(states) => {
-                states.update_from_bindings(subaction_bindings);
+                states.update_from_bindings(subaction_bindings, time, delta, is_device_active);
             }
         }
         Ok(())
@@ -418,7 +736,7 @@ impl CachedActionStatesEnum {
 }
 
 impl<T: OxideActionState> CachedActionStates<T> {
-    pub fn new(default_state: T, subaction_paths: &Vec<xr::Path>) -> Self
+    pub fn new(default_state: T, subaction_paths: &Vec<xr::Path>, user_bindings: UserBindings) -> Self
     where
         T: Clone,
     {
@@ -436,6 +754,8 @@ impl<T: OxideActionState> CachedActionStates<T> {
         Self {
             main_state: default_state,
             subaction_states,
+            user_bindings,
+            modifier_states: ModifierStates::new(),
         }
     }
 
@@ -453,16 +773,42 @@ impl<T: OxideActionState> CachedActionStates<T> {
         }
     }
 
-    pub fn update_from_bindings(&mut self, subaction_bindings: &SubactionBindings<InputBinding>) {
+    pub fn update_from_bindings(
+        &mut self,
+        subaction_bindings: &SubactionBindings<InputBinding>,
+        time: xr::Time,
+        delta: f32,
+        is_device_active: &dyn Fn(InteractionProfilePath, TopLevelUserPath) -> bool,
+    ) {
+        self.update_modifier_states(subaction_bindings, time, delta);
+
+        let all_bindings = subaction_bindings.get_matching(xr::Path::NULL).unwrap();
+        let ctx = SyncContext {
+            time,
+            delta,
+            user_bindings: &self.user_bindings,
+            modifier_states: &self.modifier_states,
+            //The whole action uses the policy of the first profile (in binding order) which sets one, whichever bindings happen to be active
+            conflict_policy: all_bindings
+                .iter()
+                .find_map(|binding| {
+                    self.user_bindings
+                        .get(&binding.action.profile_name)
+                        .and_then(|action_bindings| action_bindings.conflict_policy)
+                })
+                .unwrap_or_default(),
+            is_device_active,
+        };
+
         match subaction_bindings {
             SubactionBindings::Singleton(bindings) => {
                 debug_assert!(self.subaction_states.is_none());
 
                 self.main_state
-                    .sync_from_god_states(bindings.iter().map(|a| &a.action_state))
+                    .sync_from_god_states(bindings.iter(), &ctx)
                     .unwrap();
             }
-            SubactionBindings::Subactions(bindings_map) => {
+            SubactionBindings::Subactions(bindings_map, _) => {
                 let subaction_states = self.subaction_states.as_mut().unwrap();
                 debug_assert!(bindings_map.len() <= subaction_states.len());
 
@@ -476,16 +822,54 @@ impl<T: OxideActionState> CachedActionStates<T> {
                         })
                 {
                     states
-                        .sync_from_god_states(bindings.iter().map(|a| &a.action_state))
+                        .sync_from_god_states(bindings.iter(), &ctx)
                         .unwrap();
                 }
 
                 self.main_state
-                    .sync_from_god_states(bindings_map.values().flatten().map(|a| &a.action_state))
+                    .sync_from_god_states(all_bindings.into_iter(), &ctx)
                     .unwrap();
             }
         }
     }
+
+    ///Steps the modifiers of every binding once, bindings shared by the main and subaction states must not advance twice
+    fn update_modifier_states(
+        &mut self,
+        subaction_bindings: &SubactionBindings<InputBinding>,
+        time: xr::Time,
+        delta: f32,
+    ) {
+        let mut modifier_states = ModifierStates::new();
+
+        for binding in subaction_bindings.get_matching(xr::Path::NULL).unwrap() {
+            let modifiers = match self
+                .user_bindings
+                .get(&binding.action.profile_name)
+                .and_then(|action_bindings| action_bindings.modifiers.get(&binding.binding_str))
+            {
+                Some(modifiers) => modifiers,
+                None => continue,
+            };
+            let states = modifier_states.entry(binding.action.profile_name).or_default();
+            if states.contains_key(&binding.binding_str) {
+                continue;
+            }
+
+            let previous = self
+                .modifier_states
+                .get(&binding.action.profile_name)
+                .and_then(|states| states.get(&binding.binding_str))
+                .copied()
+                .unwrap_or_default();
+            states.insert(
+                binding.binding_str.clone(),
+                previous.step(modifiers, &binding.action_state.read().unwrap(), time, delta),
+            );
+        }
+
+        self.modifier_states = modifier_states;
+    }
 }
 
 impl GodActionStateEnum {
@@ -532,6 +916,13 @@ impl GodActionStateEnum {
 
 impl InputBinding {
     pub fn sync(&self, session: &SessionWrapper) -> Result<()> {
+        //The head is always tracked
+        if self.action.emulated == Some(EmulatedSource::HeadGaze) {
+            *self.action_state.write().unwrap() =
+                GodActionStateEnum::Pose(ActionStatePose { is_active: true });
+            return Ok(());
+        }
+
         let get_info = self.get_info();
         let result = match &mut self.action_state.write().unwrap() as &mut GodActionStateEnum {
             GodActionStateEnum::Boolean(state) => {
@@ -632,9 +1023,10 @@ pub trait OxideActionState {
     /// Float actions - The current state must be the state of the input with the largest absolute value
     ///
     /// Vector2 actions - The current state must be the state of the input with the longest length
-    fn sync_from_god_states<'a, I: Iterator<Item = &'a RwLock<GodActionStateEnum>>>(
+    fn sync_from_god_states<'a, I: Iterator<Item = &'a Arc<InputBinding>>>(
         &mut self,
-        god_states: I,
+        bindings: I,
+        ctx: &SyncContext,
     ) -> Result<()>
     where
         Self: Sized;
@@ -646,55 +1038,85 @@ pub trait OxideActionState {
 }
 
 impl OxideActionState for openxr::ActionState<bool> {
-    fn sync_from_god_states<'a, I: Iterator<Item = &'a RwLock<GodActionStateEnum>>>(
+    fn sync_from_god_states<'a, I: Iterator<Item = &'a Arc<InputBinding>>>(
         &mut self,
-        god_states: I,
+        bindings: I,
+        ctx: &SyncContext,
     ) -> Result<()>
     where
         Self: Sized,
     {
-        self.is_active = false;
         self.changed_since_last_sync = false;
 
-        let mut new_state = false;
-        let mut new_last_change_time = 0;
-
-        //The current state must be the result of a boolean OR of all bound inputs
-        for god_state in god_states
-            .map(|e| e.read().unwrap())
-            .filter(|e| e.get_inner().is_active())
-        {
-            let god_state = god_state.get_inner();
-            self.is_active = true;
-            if new_last_change_time == 0 {
-                new_last_change_time = god_state.last_change_time()?.as_nanos();
-            }
-            if god_state.get_bool()? == true {
-                new_state = true;
-                //We want the time of the earliest change to true
-                new_last_change_time = cmp::min(
-                    new_last_change_time,
-                    god_state.last_change_time()?.as_nanos(),
-                );
-            } else {
-                if new_state == false {
-                    //We want the time of the latest change to false
-                    new_last_change_time = cmp::max(
-                        new_last_change_time,
-                        god_state.last_change_time()?.as_nanos(),
-                    )
-                }
+        let mut sources = Vec::new();
+
+        for binding in bindings {
+            let god_state = binding.action_state.read().unwrap();
+            if !ctx.is_binding_active(binding, &god_state) {
+                continue;
             }
+            let decomposed = ctx.get_decomposed(binding, &god_state);
+            let value = match (ctx.get_thresholded(binding), decomposed) {
+                (Some(on), _) => on,
+                (None, Some(value)) => value > 0.5,
+                (None, None) => god_state.get_inner().get_bool()?,
+            };
+            sources.push((value, god_state.get_inner().last_change_time()?.as_nanos()));
         }
 
+        self.is_active = !sources.is_empty();
+
+        let (new_state, new_last_change_time) = match ctx.conflict_policy {
+            ConflictPolicy::LastChanged => sources
+                .iter()
+                .max_by_key(|(_, last_change_time)| *last_change_time)
+                .copied()
+                .unwrap_or((false, 0)),
+            //The current state must be the result of a boolean OR of all bound inputs
+            //The policies which combine values are rejected for booleans when the user's bindings are loaded
+            _ => {
+                let mut new_state = false;
+                let mut new_last_change_time = 0;
+
+                for (value, last_change_time) in sources {
+                    if new_last_change_time == 0 {
+                        new_last_change_time = last_change_time;
+                    }
+                    if value == true {
+                        if new_state == false {
+                            new_state = true;
+                            new_last_change_time = last_change_time;
+                        }
+                        //We want the time of the earliest change to true
+                        new_last_change_time = cmp::min(new_last_change_time, last_change_time);
+                    } else {
+                        if new_state == false {
+                            //We want the time of the latest change to false
+                            new_last_change_time = cmp::max(new_last_change_time, last_change_time)
+                        }
+                    }
+                }
+
+                (new_state, new_last_change_time)
+            }
+        };
+
         if !self.is_active {
             self.current_state = false;
             self.last_change_time = xr::Time::from_nanos(0);
         } else {
             if self.current_state != new_state {
-                debug_assert!(new_last_change_time > self.last_change_time.as_nanos()); //No time travel please, this crashes for some reason
+                let latest_change_time = sources
+                    .iter()
+                    .map(|(_, last_change_time)| *last_change_time)
+                    .max()
+                    .unwrap_or(0);
                 self.current_state = new_state;
-                self.last_change_time = xr::Time::from_nanos(new_last_change_time);
+                self.last_change_time = next_change_time(
+                    self.last_change_time,
+                    xr::Time::from_nanos(new_last_change_time),
+                    xr::Time::from_nanos(latest_change_time),
+                );
                 self.changed_since_last_sync = true;
             }
         }
@@ -720,46 +1142,56 @@ impl OxideActionState for openxr::ActionState<bool> {
 }
 
 impl OxideActionState for openxr::ActionState<f32> {
-    fn sync_from_god_states<'a, I: Iterator<Item = &'a RwLock<GodActionStateEnum>>>(
+    fn sync_from_god_states<'a, I: Iterator<Item = &'a Arc<InputBinding>>>(
         &mut self,
-        states: I,
+        bindings: I,
+        ctx: &SyncContext,
     ) -> Result<()>
     where
         Self: Sized,
     {
-        self.is_active = false;
         self.changed_since_last_sync = false;
 
-        let mut new_state = 0f32;
-        let mut new_last_change_time = xr::Time::from_nanos(0);
+        let mut sources = Vec::new();
 
-        //The current state must be the state of the input with the largest absolute value
-        for iter_state in states
-            .map(|e| e.read().unwrap())
-            .filter(|e| e.get_inner().is_active())
-        {
-            let iter_state = iter_state.get_inner();
-            self.is_active = true;
-            if iter_state.get_scalar()?.abs() >= new_state.abs() {
-                new_state = iter_state.get_scalar()?;
-                new_last_change_time = iter_state.last_change_time()?;
+        for binding in bindings {
+            let iter_state = binding.action_state.read().unwrap();
+            if !ctx.is_binding_active(binding, &iter_state) {
+                continue;
             }
+
+            let decomposed = ctx.get_decomposed(binding, &iter_state);
+            let ramped = match iter_state.deref() {
+                GodActionStateEnum::Boolean(_) => ctx.get_ramped(binding),
+                _ => None,
+            };
+            let (value, last_change_time) = match (decomposed, ramped) {
+                (Some(value), _) => (value, iter_state.get_inner().last_change_time()?),
+                (None, Some(ramped)) => ramped,
+                (None, None) => (
+                    iter_state.get_inner().get_scalar()?,
+                    iter_state.get_inner().last_change_time()?,
+                ),
+            };
+
+            sources.push((value, last_change_time));
         }
 
+        self.is_active = !sources.is_empty();
+
+        let (new_state, new_last_change_time) = merge(ctx.conflict_policy, &sources);
+
         if !self.is_active {
             self.current_state = 0f32;
             self.last_change_time = xr::Time::from_nanos(0);
         } else {
             if self.current_state != new_state {
-                //This can crash TODO estimate last_change_time when time travel occurs
-                debug_assert!(
-                    new_last_change_time.as_nanos() > self.last_change_time.as_nanos(),
-                    "{} < {}",
-                    new_last_change_time.as_nanos(),
-                    self.last_change_time.as_nanos()
-                ); //No time travel please
                 self.current_state = new_state;
-                self.last_change_time = new_last_change_time;
+                self.last_change_time = next_change_time(
+                    self.last_change_time,
+                    new_last_change_time,
+                    latest_change_time(&sources),
+                );
                 self.changed_since_last_sync = true;
             }
         }
@@ -772,7 +1204,6 @@ impl OxideActionState for openxr::ActionState<f32> {
     }
 
     fn get_bool(&self) -> Result<bool> {
-        //TODO VALVE
         Ok(self.current_state.abs() > 0.5)
     }
 
@@ -786,47 +1217,45 @@ impl OxideActionState for openxr::ActionState<f32> {
 }
 
 impl OxideActionState for openxr::ActionState<Vector2f> {
-    fn sync_from_god_states<'a, I: Iterator<Item = &'a RwLock<GodActionStateEnum>>>(
+    fn sync_from_god_states<'a, I: Iterator<Item = &'a Arc<InputBinding>>>(
         &mut self,
-        states: I,
+        bindings: I,
+        ctx: &SyncContext,
     ) -> Result<()>
     where
         Self: Sized,
     {
-        self.is_active = false;
         self.changed_since_last_sync = false;
 
-        let mut new_state = Default::default();
-        let mut new_last_change_time = xr::Time::from_nanos(0);
+        let mut sources = Vec::new();
 
-        fn len2(vec: openxr::Vector2f) -> f32 {
-            return vec.x * vec.x + vec.y * vec.y;
-        }
-
-        //The current state must be the state of the input with the longest length
-        for iter_state in states
-            .map(|e| e.read().unwrap())
-            .filter(|e| e.get_inner().is_active())
-        {
+        for binding in bindings {
+            let iter_state = binding.action_state.read().unwrap();
+            if !ctx.is_binding_active(binding, &iter_state) {
+                continue;
+            }
             if let GodActionStateEnum::Vector2f(iter_state) = iter_state.deref() {
-                self.is_active = true;
-                if len2(iter_state.current_state) >= len2(new_state) {
-                    new_state = iter_state.current_state;
-                    new_last_change_time = iter_state.last_change_time;
-                }
+                sources.push((iter_state.current_state, iter_state.last_change_time));
             } else {
                 panic!();
             }
         }
 
+        self.is_active = !sources.is_empty();
+
+        let (new_state, new_last_change_time) = merge(ctx.conflict_policy, &sources);
+
         if !self.is_active {
             self.current_state = Default::default();
             self.last_change_time = xr::Time::from_nanos(0);
         } else {
             if self.current_state != new_state {
-                debug_assert!(new_last_change_time.as_nanos() > self.last_change_time.as_nanos()); //No time travel please
                 self.current_state = new_state;
-                self.last_change_time = new_last_change_time;
+                self.last_change_time = next_change_time(
+                    self.last_change_time,
+                    new_last_change_time,
+                    latest_change_time(&sources),
+                );
                 self.changed_since_last_sync = true;
             }
         }
@@ -852,18 +1281,16 @@ impl OxideActionState for openxr::ActionState<Vector2f> {
 }
 
 impl OxideActionState for ActionStatePose {
-    fn sync_from_god_states<'a, I: Iterator<Item = &'a RwLock<GodActionStateEnum>>>(
+    fn sync_from_god_states<'a, I: Iterator<Item = &'a Arc<InputBinding>>>(
         &mut self,
-        states: I,
+        mut bindings: I,
+        ctx: &SyncContext,
     ) -> Result<()>
     where
         Self: Sized,
     {
-        self.is_active = states
-            .map(|e| e.read().unwrap())
-            .filter(|e| e.get_inner().is_active())
-            .next()
-            .is_some();
+        self.is_active = bindings
+            .any(|binding| ctx.is_binding_active(binding, &binding.action_state.read().unwrap()));
         Ok(())
     }
 
@@ -883,3 +1310,1001 @@ impl OxideActionState for ActionStatePose {
         Err(xr::Result::ERROR_ACTION_TYPE_MISMATCH)
     }
 }
+
+///A state which the states of multiple bindings can be merged into
+trait MergeableState: Copy + Default {
+    fn magnitude(&self) -> f32;
+    fn combine(self, other: Self) -> Self;
+    fn clamp_magnitude(self) -> Self;
+}
+
+impl MergeableState for f32 {
+    fn magnitude(&self) -> f32 {
+        self.abs()
+    }
+
+    fn combine(self, other: Self) -> Self {
+        self + other
+    }
+
+    fn clamp_magnitude(self) -> Self {
+        self.max(-1f32).min(1f32)
+    }
+}
+
+impl MergeableState for Vector2f {
+    fn magnitude(&self) -> f32 {
+        (self.x * self.x + self.y * self.y).sqrt()
+    }
+
+    fn combine(self, other: Self) -> Self {
+        Vector2f {
+            x: self.x + other.x,
+            y: self.y + other.y,
+        }
+    }
+
+    fn clamp_magnitude(self) -> Self {
+        let magnitude = self.magnitude();
+        if magnitude > 1f32 {
+            Vector2f {
+                x: self.x / magnitude,
+                y: self.y / magnitude,
+            }
+        } else {
+            self
+        }
+    }
+}
+
+///The subaction path a binding feeds, the user may route any binding to any subaction path of the action, otherwise it goes to the one it belongs to
+fn route_binding(
+    binding_str: &str,
+    route: Option<xr::Path>,
+    subaction_paths: &[(xr::Path, String)],
+) -> Option<xr::Path> {
+    if let Some(route) = route {
+        if subaction_paths.iter().any(|(subaction_path, _)| *subaction_path == route) {
+            return Some(route);
+        }
+    }
+
+    subaction_paths
+        .iter()
+        .find(|(_, subaction_path_str)| {
+            binding_str.starts_with(subaction_path_str.as_str())
+                && binding_str[subaction_path_str.len()..].starts_with('/')
+        })
+        .map(|(subaction_path, _)| *subaction_path)
+}
+
+///Resolves the states of every active binding (in the order they were bound) into a single state
+fn merge<T: MergeableState>(policy: ConflictPolicy, sources: &[(T, xr::Time)]) -> (T, xr::Time) {
+    let mut merged = (T::default(), xr::Time::from_nanos(0));
+
+    match policy {
+        //The current state must be the state of the input with the largest absolute value / longest length
+        ConflictPolicy::Default => {
+            for source in sources {
+                if source.0.magnitude() >= merged.0.magnitude() {
+                    merged = *source;
+                }
+            }
+        }
+        ConflictPolicy::Sum | ConflictPolicy::ClampedSum => {
+            for (value, last_change_time) in sources {
+                merged.0 = merged.0.combine(*value);
+                merged.1 = xr::Time::from_nanos(cmp::max(
+                    merged.1.as_nanos(),
+                    last_change_time.as_nanos(),
+                ));
+            }
+            if policy == ConflictPolicy::ClampedSum {
+                merged.0 = merged.0.clamp_magnitude();
+            }
+        }
+        ConflictPolicy::Priority => {
+            if let Some(source) = sources
+                .iter()
+                .find(|(value, _)| value.magnitude() > 0f32)
+                .or(sources.first())
+            {
+                merged = *source;
+            }
+        }
+        ConflictPolicy::LastChanged => {
+            if let Some(source) = sources
+                .iter()
+                .max_by_key(|(_, last_change_time)| last_change_time.as_nanos())
+            {
+                merged = *source;
+            }
+        }
+    }
+
+    merged
+}
+
+fn latest_change_time<T>(sources: &[(T, xr::Time)]) -> xr::Time {
+    xr::Time::from_nanos(
+        sources
+            .iter()
+            .map(|(_, last_change_time)| last_change_time.as_nanos())
+            .max()
+            .unwrap_or(0),
+    )
+}
+
+///Chooses the last_change_time of a merged state which has just changed
+///
+///The time picked by the conflict policy can be older than the previous change (e.g. when the winning binding switches to one which has been held for a while).
+///A merged state can only change when one of its bindings does, so in that case the most recent change of any binding is used instead.
+fn next_change_time(previous: xr::Time, chosen: xr::Time, latest: xr::Time) -> xr::Time {
+    if chosen.as_nanos() > previous.as_nanos() {
+        chosen
+    } else if latest.as_nanos() > previous.as_nanos() {
+        latest
+    } else {
+        //Only reachable when a ramp has already reported a change at a sync time after the latest change of any binding
+        xr::Time::from_nanos(previous.as_nanos() + 1)
+    }
+}
+
+///Moves `from` towards `to` at the speed given by the ramp
+fn apply_ramp(ramp: &Ramp, from: f32, to: f32, delta: f32) -> f32 {
+    let duration = if to > from { ramp.ramp_up } else { ramp.ramp_down };
+    if duration <= 0f32 {
+        return to;
+    }
+
+    let step = delta / duration;
+    if to > from {
+        (from + step).min(to)
+    } else {
+        (from - step).max(to)
+    }
+}
+
+fn decompose(decomposition: &Decomposition, vec: Vector2f) -> f32 {
+    match decomposition {
+        Decomposition::Magnitude => (vec.x * vec.x + vec.y * vec.y).sqrt().min(1f32),
+        Decomposition::Sector {
+            center,
+            width,
+            deadzone,
+        } => {
+            if (vec.x * vec.x + vec.y * vec.y).sqrt() < *deadzone {
+                return 0f32;
+            }
+            let angle = vec.y.atan2(vec.x).to_degrees();
+            //Signed difference between the two angles wrapped to -180..180
+            let difference = (angle - center + 540f32).rem_euclid(360f32) - 180f32;
+            if difference.abs() <= width / 2f32 {
+                1f32
+            } else {
+                0f32
+            }
+        }
+        Decomposition::Axis { axis, sign } => {
+            let value = match axis {
+                Axis::X => vec.x,
+                Axis::Y => vec.y,
+            };
+            match sign {
+                AxisSign::Both => value,
+                AxisSign::Positive => value.max(0f32),
+                AxisSign::Negative => (-value).max(0f32),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use common::application_bindings::AnalogThreshold;
+
+    ///Tiny xorshift generator so the property tests are reproducible without any extra dependencies
+    struct Rng(u64);
+
+    impl Rng {
+        fn next(&mut self) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0
+        }
+
+        fn range(&mut self, max: u64) -> u64 {
+            self.next() % max
+        }
+
+        ///A value from -1.0 to 1.0 in steps of 0.5 so that ties and repeats happen often
+        fn axis(&mut self) -> f32 {
+            self.range(5) as f32 * 0.5 - 1f32
+        }
+    }
+
+    const POLICIES: [ConflictPolicy; 5] = [
+        ConflictPolicy::Default,
+        ConflictPolicy::Sum,
+        ConflictPolicy::ClampedSum,
+        ConflictPolicy::Priority,
+        ConflictPolicy::LastChanged,
+    ];
+
+    fn binding(action_type: ActionType) -> Arc<InputBinding> {
+        named_binding(action_type, "")
+    }
+
+    fn named_binding(action_type: ActionType, binding_str: &str) -> Arc<InputBinding> {
+        profile_binding(action_type, xr::Path::NULL, xr::Path::NULL, binding_str)
+    }
+
+    fn profile_binding(
+        action_type: ActionType,
+        profile_name: xr::Path,
+        subaction_path: xr::Path,
+        binding_str: &str,
+    ) -> Arc<InputBinding> {
+        Arc::new(InputBinding {
+            action: Arc::new(GodAction {
+                handle: xr::Action::NULL,
+                profile_name_str: String::new(),
+                profile_name,
+                name: String::new(),
+                subaction_paths: Vec::new(),
+                action_type,
+                emulated: None,
+            }),
+            binding_str: binding_str.to_owned(),
+            subaction_path,
+            action_state: RwLock::new(GodActionStateEnum::new(action_type).unwrap()),
+        })
+    }
+
+    ///Randomly changes, activates and deactivates the bindings of an action then checks that every sync reports a sane last_change_time
+    fn check_last_change_time<V, F>(action_type: ActionType, random_state: F)
+    where
+        V: Copy + Default + PartialEq + std::fmt::Debug,
+        openxr::ActionState<V>: OxideActionState,
+        F: Fn(&mut Rng, bool, xr::Time) -> GodActionStateEnum,
+    {
+        for policy in POLICIES.iter() {
+            let mut user_bindings = UserBindings::new();
+            user_bindings.insert(
+                xr::Path::NULL,
+                ActionBindings {
+                    conflict_policy: Some(*policy),
+                    ..Default::default()
+                },
+            );
+
+            for seed in 1..200 {
+                let mut rng = Rng(seed);
+                let bindings = (0..3).map(|_| binding(action_type)).collect::<Vec<_>>();
+                let mut state = openxr::ActionState::<V> {
+                    current_state: V::default(),
+                    changed_since_last_sync: false,
+                    last_change_time: xr::Time::from_nanos(0),
+                    is_active: false,
+                };
+                let mut now = 1_000_000;
+
+                for _ in 0..100 {
+                    let previous_now = now;
+                    now += 1 + rng.range(20_000_000) as i64;
+
+                    for binding in &bindings {
+                        if rng.range(3) == 0 {
+                            let is_active = rng.range(8) != 0;
+                            let changed_at =
+                                xr::Time::from_nanos(previous_now + 1 + rng.range((now - previous_now) as u64) as i64);
+                            *binding.action_state.write().unwrap() =
+                                random_state(&mut rng, is_active, changed_at);
+                        }
+                    }
+
+                    let previous = state;
+                    state
+                        .sync_from_god_states(
+                            bindings.iter(),
+                            &SyncContext {
+                                time: xr::Time::from_nanos(now),
+                                delta: 0f32,
+                                user_bindings: &user_bindings,
+                                modifier_states: &ModifierStates::new(),
+                                conflict_policy: *policy,
+                                is_device_active: &|_, _| true,
+                            },
+                        )
+                        .unwrap();
+
+                    if !state.is_active {
+                        continue;
+                    }
+                    assert!(state.last_change_time.as_nanos() <= now, "{:?}: change reported in the future", policy);
+                    if previous.is_active {
+                        assert_eq!(state.changed_since_last_sync, previous.current_state != state.current_state);
+                        if state.changed_since_last_sync {
+                            assert!(
+                                state.last_change_time.as_nanos() > previous.last_change_time.as_nanos(),
+                                "{:?}: {:?} -> {:?} went back in time from {} to {}",
+                                policy,
+                                previous.current_state,
+                                state.current_state,
+                                previous.last_change_time.as_nanos(),
+                                state.last_change_time.as_nanos(),
+                            );
+                        } else {
+                            assert_eq!(state.last_change_time, previous.last_change_time);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn boolean_last_change_time() {
+        check_last_change_time::<bool, _>(ActionType::BooleanInput, |rng, is_active, changed_at| {
+            GodActionStateEnum::Boolean(openxr::ActionState {
+                current_state: rng.range(2) == 0,
+                changed_since_last_sync: true,
+                last_change_time: changed_at,
+                is_active,
+            })
+        });
+    }
+
+    #[test]
+    fn float_last_change_time() {
+        check_last_change_time::<f32, _>(ActionType::FloatInput, |rng, is_active, changed_at| {
+            GodActionStateEnum::Float(openxr::ActionState {
+                current_state: rng.axis(),
+                changed_since_last_sync: true,
+                last_change_time: changed_at,
+                is_active,
+            })
+        });
+    }
+
+    #[test]
+    fn vector2f_last_change_time() {
+        check_last_change_time::<Vector2f, _>(ActionType::Vector2fInput, |rng, is_active, changed_at| {
+            GodActionStateEnum::Vector2f(openxr::ActionState {
+                current_state: Vector2f {
+                    x: rng.axis(),
+                    y: rng.axis(),
+                },
+                changed_since_last_sync: true,
+                last_change_time: changed_at,
+                is_active,
+            })
+        });
+    }
+
+    #[test]
+    fn ramp_per_binding() {
+        let hand = xr::Path::from_raw(1);
+        let ramped = named_binding(ActionType::BooleanInput, "/user/hand/left/input/a/click");
+        let held = named_binding(ActionType::FloatInput, "/user/hand/left/input/trigger/value");
+
+        let mut user_bindings = UserBindings::new();
+        user_bindings.insert(
+            xr::Path::NULL,
+            ActionBindings {
+                modifiers: vec![(
+                    ramped.binding_str.clone(),
+                    BindingModifiers {
+                        ramp: Some(Ramp {
+                            ramp_up: 1f32,
+                            ramp_down: 0.5f32,
+                        }),
+                        ..Default::default()
+                    },
+                )]
+                .into_iter()
+                .collect(),
+                ..Default::default()
+            },
+        );
+
+        let mut states = CachedActionStates::new(
+            openxr::ActionState::<f32> {
+                current_state: 0f32,
+                changed_since_last_sync: false,
+                last_change_time: xr::Time::from_nanos(0),
+                is_active: false,
+            },
+            &vec![hand],
+            user_bindings,
+        );
+        //The ramped binding is shared by the subaction and main states, it must still only move once per sync
+        let bindings = SubactionBindings::Subactions(
+            vec![(hand, vec![ramped.clone(), held.clone()])]
+                .into_iter()
+                .collect(),
+            vec![ramped.clone(), held.clone()],
+        );
+
+        *ramped.action_state.write().unwrap() = GodActionStateEnum::Boolean(openxr::ActionState {
+            current_state: true,
+            changed_since_last_sync: true,
+            last_change_time: xr::Time::from_nanos(1),
+            is_active: true,
+        });
+        *held.action_state.write().unwrap() = GodActionStateEnum::Float(openxr::ActionState {
+            current_state: 0.6f32,
+            changed_since_last_sync: true,
+            last_change_time: xr::Time::from_nanos(1),
+            is_active: true,
+        });
+
+        let sync = |states: &mut CachedActionStates<openxr::ActionState<f32>>, now: i64, expected: f32| {
+            states.update_from_bindings(&bindings, xr::Time::from_nanos(now), 0.25, &|_, _| true);
+            for state in [&states.main_state, &states.subaction_states.as_ref().unwrap()[&hand]] {
+                assert!((state.current_state - expected).abs() < 1e-6, "{} != {}", state.current_state, expected);
+                assert!(state.last_change_time.as_nanos() <= now);
+            }
+        };
+
+        //The ramp climbs from its own value rather than from the 0.6 of the other binding
+        sync(&mut states, 100, 0.6);
+        sync(&mut states, 200, 0.6);
+        sync(&mut states, 300, 0.75);
+        sync(&mut states, 400, 1.0);
+        sync(&mut states, 500, 1.0);
+
+        *ramped.action_state.write().unwrap() = GodActionStateEnum::Boolean(openxr::ActionState {
+            current_state: false,
+            changed_since_last_sync: true,
+            last_change_time: xr::Time::from_nanos(550),
+            is_active: true,
+        });
+        sync(&mut states, 600, 0.6);
+        assert_eq!(states.modifier_states[&xr::Path::NULL][&ramped.binding_str].ramp, 0.5);
+        sync(&mut states, 700, 0.6);
+        assert_eq!(states.modifier_states[&xr::Path::NULL][&ramped.binding_str].ramp, 0.0);
+    }
+
+    #[test]
+    fn analog_threshold_per_binding() {
+        let first = named_binding(ActionType::FloatInput, "/user/hand/left/input/trigger/value");
+        let second = named_binding(ActionType::FloatInput, "/user/hand/left/input/squeeze/value");
+
+        let threshold = BindingModifiers {
+            analog_threshold: Some(AnalogThreshold {
+                on_threshold: 0.7f32,
+                off_threshold: 0.3f32,
+            }),
+            ..Default::default()
+        };
+        let mut user_bindings = UserBindings::new();
+        user_bindings.insert(
+            xr::Path::NULL,
+            ActionBindings {
+                modifiers: vec![
+                    (first.binding_str.clone(), threshold.clone()),
+                    (second.binding_str.clone(), threshold),
+                ]
+                .into_iter()
+                .collect(),
+                ..Default::default()
+            },
+        );
+
+        let mut states = CachedActionStates::new(
+            openxr::ActionState::<bool> {
+                current_state: false,
+                changed_since_last_sync: false,
+                last_change_time: xr::Time::from_nanos(0),
+                is_active: false,
+            },
+            &Vec::new(),
+            user_bindings,
+        );
+        let bindings = SubactionBindings::Singleton(vec![first.clone(), second.clone()]);
+
+        let set = |binding: &InputBinding, value: f32, time: i64| {
+            *binding.action_state.write().unwrap() = GodActionStateEnum::Float(openxr::ActionState {
+                current_state: value,
+                changed_since_last_sync: true,
+                last_change_time: xr::Time::from_nanos(time),
+                is_active: true,
+            });
+        };
+
+        set(&first, 0.8, 1);
+        set(&second, 0.5, 1);
+        states.update_from_bindings(&bindings, xr::Time::from_nanos(10), 0f32, &|_, _| true);
+        assert!(states.main_state.current_state);
+
+        //The second binding never crossed its on threshold, the first one being pressed mustn't hold it down
+        set(&first, 0.5, 15);
+        states.update_from_bindings(&bindings, xr::Time::from_nanos(20), 0f32, &|_, _| true);
+        assert!(states.main_state.current_state);
+        set(&first, 0.1, 25);
+        states.update_from_bindings(&bindings, xr::Time::from_nanos(30), 0f32, &|_, _| true);
+        assert!(!states.main_state.current_state);
+
+        set(&second, 0.8, 35);
+        states.update_from_bindings(&bindings, xr::Time::from_nanos(40), 0f32, &|_, _| true);
+        assert!(states.main_state.current_state);
+        set(&second, 0.5, 45);
+        states.update_from_bindings(&bindings, xr::Time::from_nanos(50), 0f32, &|_, _| true);
+        assert!(states.main_state.current_state);
+    }
+
+    #[test]
+    fn analog_threshold_haptics() {
+        let trigger = named_binding(ActionType::FloatInput, "/user/hand/left/input/trigger/value");
+
+        let modifiers = BindingModifiers {
+            analog_threshold: Some(AnalogThreshold {
+                on_threshold: 0.6f32,
+                off_threshold: 0.4f32,
+            }),
+            ..Default::default()
+        };
+        let mut user_bindings = UserBindings::new();
+        user_bindings.insert(
+            xr::Path::NULL,
+            ActionBindings {
+                modifiers: vec![(trigger.binding_str.clone(), modifiers.clone())]
+                    .into_iter()
+                    .collect(),
+                ..Default::default()
+            },
+        );
+
+        let mut states = CachedActionStates::new(
+            openxr::ActionState::<f32> {
+                current_state: 0f32,
+                changed_since_last_sync: false,
+                last_change_time: xr::Time::from_nanos(0),
+                is_active: false,
+            },
+            &Vec::new(),
+            user_bindings,
+        );
+        let bindings = SubactionBindings::Singleton(vec![trigger.clone()]);
+
+        //(value, press haptic, release haptic)
+        let syncs = [
+            (0.0, false, false),
+            (0.5, false, false),
+            (0.7, true, false),
+            (0.8, false, false),
+            (0.5, false, false),
+            (0.3, false, true),
+            (0.2, false, false),
+            (0.5, false, false),
+            (0.65, true, false),
+        ];
+        for (i, (value, press, release)) in syncs.iter().enumerate() {
+            let now = time((i as i64 + 1) * 10_000_000);
+            *trigger.action_state.write().unwrap() = GodActionStateEnum::Float(openxr::ActionState {
+                current_state: *value,
+                changed_since_last_sync: true,
+                last_change_time: now,
+                is_active: true,
+            });
+            states.update_from_bindings(&bindings, now, 0.01, &|_, _| true);
+
+            let modifier_state = states.modifier_states[&xr::Path::NULL].get(&trigger.binding_str);
+            let pressed = get_pressed_state(&trigger.action_state.read().unwrap(), &modifiers, modifier_state).unwrap();
+            assert_eq!(is_triggered(TriggerCondition::Press, &pressed, now, 0.01), *press, "press at {}", value);
+            assert_eq!(is_triggered(TriggerCondition::Release, &pressed, now, 0.01), *release, "release at {}", value);
+        }
+
+        //Holds are timed from the threshold crossing, not the latest change of the value
+        let crossed = states.modifier_states[&xr::Path::NULL][&trigger.binding_str].threshold_change_time;
+        assert_eq!(crossed.as_nanos(), 90_000_000);
+        let pressed = get_pressed_state(&trigger.action_state.read().unwrap(), &modifiers, Some(&ModifierState {
+            threshold_changed: false,
+            ..states.modifier_states[&xr::Path::NULL][&trigger.binding_str]
+        })).unwrap();
+        let long_press = TriggerCondition::LongPress { seconds: 0.02 };
+        assert!(!is_triggered(long_press, &pressed, time(100_000_000), 0.01));
+        assert!(is_triggered(long_press, &pressed, time(110_000_000), 0.01));
+    }
+
+    #[test]
+    fn god_suggestion_has_no_next_chain() {
+        let bindings = [
+            xr::ActionSuggestedBinding {
+                action: xr::Action::from_raw(1),
+                binding: xr::Path::from_raw(2),
+            },
+            xr::ActionSuggestedBinding {
+                action: xr::Action::from_raw(1),
+                binding: xr::Path::from_raw(3),
+            },
+        ];
+        let suggestion = god_suggestion(xr::Path::from_raw(4), &bindings);
+        assert!(suggestion.next.is_null());
+        assert_eq!(suggestion.interaction_profile.into_raw(), 4);
+        assert_eq!(suggestion.count_suggested_bindings, 2);
+        assert_eq!(suggestion.suggested_bindings, bindings.as_ptr());
+    }
+
+    fn time(nanos: i64) -> xr::Time {
+        xr::Time::from_nanos(nanos)
+    }
+
+    #[test]
+    fn merge_floats() {
+        let sources = [(0f32, time(4)), (0.6, time(1)), (0.7, time(2)), (-0.2, time(3))];
+        let expected = [
+            (ConflictPolicy::Default, 0.7, 2),
+            (ConflictPolicy::Sum, 1.1, 4),
+            (ConflictPolicy::ClampedSum, 1.0, 4),
+            (ConflictPolicy::Priority, 0.6, 1),
+            (ConflictPolicy::LastChanged, 0.0, 4),
+        ];
+        for (policy, value, last_change_time) in expected.iter() {
+            let merged = merge(*policy, &sources);
+            assert!((merged.0 - value).abs() < 1e-6, "{:?}: {} != {}", policy, merged.0, value);
+            assert_eq!(merged.1.as_nanos(), *last_change_time, "{:?}", policy);
+        }
+    }
+
+    #[test]
+    fn merge_vectors() {
+        let vector = |x, y| Vector2f { x, y };
+        let sources = [
+            (vector(0.0, 0.0), time(4)),
+            (vector(0.6, 0.0), time(1)),
+            (vector(0.0, 0.7), time(2)),
+            (vector(0.6, 0.8), time(3)),
+        ];
+        let expected = [
+            (ConflictPolicy::Default, vector(0.6, 0.8), 3),
+            (ConflictPolicy::Sum, vector(1.2, 1.5), 4),
+            (ConflictPolicy::ClampedSum, vector(0.624695, 0.780869), 4),
+            (ConflictPolicy::Priority, vector(0.6, 0.0), 1),
+            (ConflictPolicy::LastChanged, vector(0.0, 0.0), 4),
+        ];
+        for (policy, value, last_change_time) in expected.iter() {
+            let merged = merge(*policy, &sources);
+            assert!(
+                (merged.0.x - value.x).abs() < 1e-5 && (merged.0.y - value.y).abs() < 1e-5,
+                "{:?}: {:?} != {:?}",
+                policy,
+                merged.0,
+                value
+            );
+            assert_eq!(merged.1.as_nanos(), *last_change_time, "{:?}", policy);
+        }
+    }
+
+    #[test]
+    fn merge_booleans() {
+        let bindings = [(false, 5), (true, 1), (false, 3)]
+            .iter()
+            .map(|(value, last_change_time)| {
+                let binding = binding(ActionType::BooleanInput);
+                *binding.action_state.write().unwrap() = GodActionStateEnum::Boolean(openxr::ActionState {
+                    current_state: *value,
+                    changed_since_last_sync: true,
+                    last_change_time: time(*last_change_time),
+                    is_active: true,
+                });
+                binding
+            })
+            .collect::<Vec<_>>();
+
+        let expected = [
+            (ConflictPolicy::Default, true, 1),
+            (ConflictPolicy::LastChanged, false, 5),
+        ];
+        for (policy, value, last_change_time) in expected.iter() {
+            let mut state = openxr::ActionState::<bool> {
+                current_state: !value,
+                changed_since_last_sync: false,
+                last_change_time: time(0),
+                is_active: true,
+            };
+            state
+                .sync_from_god_states(
+                    bindings.iter(),
+                    &SyncContext {
+                        time: time(10),
+                        delta: 0f32,
+                        user_bindings: &UserBindings::new(),
+                        modifier_states: &ModifierStates::new(),
+                        conflict_policy: *policy,
+                        is_device_active: &|_, _| true,
+                    },
+                )
+                .unwrap();
+            assert_eq!(state.current_state, *value, "{:?}", policy);
+            assert_eq!(state.last_change_time.as_nanos(), *last_change_time, "{:?}", policy);
+        }
+    }
+
+    #[test]
+    fn priority_follows_binding_order() {
+        let left = xr::Path::from_raw(1);
+        let right = xr::Path::from_raw(2);
+        let left_trigger = profile_binding(ActionType::FloatInput, xr::Path::NULL, left, "/user/hand/left/input/trigger/value");
+        let right_trigger = profile_binding(ActionType::FloatInput, xr::Path::NULL, right, "/user/hand/right/input/trigger/value");
+
+        let mut user_bindings = UserBindings::new();
+        user_bindings.insert(
+            xr::Path::NULL,
+            ActionBindings {
+                conflict_policy: Some(ConflictPolicy::Priority),
+                ..Default::default()
+            },
+        );
+        let mut states = CachedActionStates::new(
+            openxr::ActionState::<f32> {
+                current_state: 0f32,
+                changed_since_last_sync: false,
+                last_change_time: xr::Time::from_nanos(0),
+                is_active: false,
+            },
+            &vec![left, right],
+            user_bindings,
+        );
+        //The user listed the right trigger first
+        let bindings = SubactionBindings::Subactions(
+            vec![(left, vec![left_trigger.clone()]), (right, vec![right_trigger.clone()])]
+                .into_iter()
+                .collect(),
+            vec![right_trigger.clone(), left_trigger.clone()],
+        );
+        assert_eq!(
+            bindings
+                .get_matching(xr::Path::NULL)
+                .unwrap()
+                .iter()
+                .map(|binding| binding.subaction_path.into_raw())
+                .collect::<Vec<_>>(),
+            vec![2, 1]
+        );
+
+        for (binding, value) in [(&left_trigger, 0.3f32), (&right_trigger, 0.8f32)] {
+            *binding.action_state.write().unwrap() = GodActionStateEnum::Float(openxr::ActionState {
+                current_state: value,
+                changed_since_last_sync: true,
+                last_change_time: time(1),
+                is_active: true,
+            });
+        }
+        states.update_from_bindings(&bindings, time(10), 0f32, &|_, _| true);
+        assert_eq!(states.main_state.current_state, 0.8);
+        assert_eq!(states.subaction_states.as_ref().unwrap()[&left].current_state, 0.3);
+    }
+
+    #[test]
+    fn conflict_policy_per_action() {
+        let first_profile = xr::Path::from_raw(1);
+        let second_profile = xr::Path::from_raw(2);
+        let first = profile_binding(ActionType::FloatInput, first_profile, xr::Path::NULL, "/user/hand/left/input/trigger/value");
+        let second = profile_binding(ActionType::FloatInput, second_profile, xr::Path::NULL, "/user/hand/left/input/trigger/value");
+
+        let mut user_bindings = UserBindings::new();
+        user_bindings.insert(first_profile, ActionBindings::default());
+        user_bindings.insert(
+            second_profile,
+            ActionBindings {
+                conflict_policy: Some(ConflictPolicy::Sum),
+                ..Default::default()
+            },
+        );
+        let mut states = CachedActionStates::new(
+            openxr::ActionState::<f32> {
+                current_state: 0f32,
+                changed_since_last_sync: false,
+                last_change_time: time(0),
+                is_active: false,
+            },
+            &Vec::new(),
+            user_bindings,
+        );
+        let bindings = SubactionBindings::Singleton(vec![first.clone(), second.clone()]);
+
+        for (binding, value) in [(&first, 0.3f32), (&second, 0.4f32)].iter() {
+            *binding.action_state.write().unwrap() = GodActionStateEnum::Float(openxr::ActionState {
+                current_state: *value,
+                changed_since_last_sync: true,
+                last_change_time: time(1),
+                is_active: true,
+            });
+        }
+
+        //The first binding's profile doesn't set a policy so the second one's applies to both
+        for _ in 0..2 {
+            states.update_from_bindings(&bindings, time(10), 0f32, &|_, _| true);
+            assert!((states.main_state.current_state - 0.7).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn decompose_magnitude() {
+        let vector = |x, y| Vector2f { x, y };
+        assert!((decompose(&Decomposition::Magnitude, vector(0.3, 0.4)) - 0.5).abs() < 1e-6);
+        assert!((decompose(&Decomposition::Magnitude, vector(-0.6, -0.8)) - 1.0).abs() < 1e-6);
+        //Square gates can report more than a unit length in the corners
+        assert_eq!(decompose(&Decomposition::Magnitude, vector(0.9, 0.9)), 1.0);
+    }
+
+    #[test]
+    fn decompose_sector() {
+        let vector = |x, y| Vector2f { x, y };
+        let up = Decomposition::Sector {
+            center: 90f32,
+            width: 90f32,
+            deadzone: 0.2f32,
+        };
+        assert_eq!(decompose(&up, vector(0.0, 1.0)), 1.0);
+        assert_eq!(decompose(&up, vector(0.6, 0.7)), 1.0);
+        assert_eq!(decompose(&up, vector(0.7, 0.6)), 0.0);
+        assert_eq!(decompose(&up, vector(0.0, -1.0)), 0.0);
+        assert_eq!(decompose(&up, vector(0.0, 0.1)), 0.0);
+
+        //Sectors around 180 degrees cover both sides of where atan2 wraps
+        let left = Decomposition::Sector {
+            center: 180f32,
+            width: 60f32,
+            deadzone: 0f32,
+        };
+        assert_eq!(decompose(&left, vector(-1.0, 0.1)), 1.0);
+        assert_eq!(decompose(&left, vector(-1.0, -0.1)), 1.0);
+        assert_eq!(decompose(&left, vector(1.0, 0.0)), 0.0);
+    }
+
+    #[test]
+    fn decompose_axis() {
+        let vector = |x, y| Vector2f { x, y };
+        let axis = |axis, sign| Decomposition::Axis { axis, sign };
+        let position = vector(-0.5, 0.25);
+        assert_eq!(decompose(&axis(Axis::X, AxisSign::Both), position), -0.5);
+        assert_eq!(decompose(&axis(Axis::X, AxisSign::Positive), position), 0.0);
+        assert_eq!(decompose(&axis(Axis::X, AxisSign::Negative), position), 0.5);
+        assert_eq!(decompose(&axis(Axis::Y, AxisSign::Both), position), 0.25);
+        assert_eq!(decompose(&axis(Axis::Y, AxisSign::Positive), position), 0.25);
+        assert_eq!(decompose(&axis(Axis::Y, AxisSign::Negative), position), 0.0);
+    }
+
+    #[test]
+    fn blended_devices() {
+        let hand = xr::Path::from_raw(1);
+        let controller = xr::Path::from_raw(2);
+        let hand_tracking = xr::Path::from_raw(3);
+        let trigger = profile_binding(ActionType::FloatInput, controller, hand, "/user/hand/left/input/trigger/value");
+        let pinch = profile_binding(ActionType::FloatInput, hand_tracking, hand, "/user/hand/left/input/pinch_ext/value");
+
+        let mut session = SessionWrapper::default();
+        session.active_profiles.insert(
+            TopLevelUserPath(hand),
+            RwLock::new(InteractionProfilePath(controller)),
+        );
+
+        let mut states = CachedActionStates::new(
+            openxr::ActionState::<f32> {
+                current_state: 0f32,
+                changed_since_last_sync: false,
+                last_change_time: time(0),
+                is_active: false,
+            },
+            &Vec::new(),
+            UserBindings::new(),
+        );
+        for (binding, value) in [(&trigger, 0.3f32), (&pinch, 0.8f32)].iter() {
+            *binding.action_state.write().unwrap() = GodActionStateEnum::Float(openxr::ActionState {
+                current_state: *value,
+                changed_since_last_sync: true,
+                last_change_time: time(1),
+                is_active: true,
+            });
+        }
+        let bindings = SubactionBindings::Singleton(vec![trigger.clone(), pinch.clone()]);
+        let is_device_active =
+            |profile: InteractionProfilePath, user_path: TopLevelUserPath| session.is_device_active(profile, user_path);
+
+        //Only the current profile of the user path counts until its devices are blended
+        states.update_from_bindings(&bindings, time(10), 0f32, &is_device_active);
+        assert!((states.main_state.current_state - 0.3).abs() < 1e-6);
+
+        session.blended_devices.write().unwrap().insert((
+            InteractionProfilePath(hand_tracking),
+            TopLevelUserPath(hand),
+        ));
+        states.update_from_bindings(&bindings, time(20), 0f32, &is_device_active);
+        assert!((states.main_state.current_state - 0.8).abs() < 1e-6);
+    }
+
+    #[test]
+    fn blending_follows_reported_inputs() {
+        let hand = xr::Path::from_raw(1);
+        let controller = xr::Path::from_raw(2);
+        let hand_tracking = xr::Path::from_raw(3);
+        let trigger = profile_binding(ActionType::FloatInput, controller, hand, "/user/hand/left/input/trigger/value");
+        let pinch = profile_binding(ActionType::FloatInput, hand_tracking, hand, "/user/hand/left/input/pinch_ext/value");
+
+        let mut session = SessionWrapper::default();
+        session.active_profiles.insert(
+            TopLevelUserPath(hand),
+            RwLock::new(InteractionProfilePath(controller)),
+        );
+        for (binding, binding_path) in [(&trigger, 4), (&pinch, 5)].iter() {
+            session
+                .god_states
+                .entry(binding.action.profile_name)
+                .or_default()
+                .insert(xr::Path::from_raw(*binding_path), (*binding).clone());
+        }
+        let set_active = |binding: &Arc<InputBinding>, is_active| {
+            *binding.action_state.write().unwrap() = GodActionStateEnum::Float(openxr::ActionState {
+                current_state: 0.5f32,
+                changed_since_last_sync: false,
+                last_change_time: time(1),
+                is_active,
+            });
+        };
+        let is_blended = |profile| {
+            session
+                .blended_devices
+                .read()
+                .unwrap()
+                .contains(&(InteractionProfilePath(profile), TopLevelUserPath(hand)))
+        };
+
+        //The hand tracking device isn't the active profile of the hand and the runtime reports its inputs as inactive
+        set_active(&trigger, true);
+        set_active(&pinch, false);
+        session.blend_devices(&[hand]);
+        assert!(is_blended(controller));
+        assert!(!is_blended(hand_tracking));
+
+        //Once the runtime reports its inputs as active it is blended despite not being the active profile
+        set_active(&pinch, true);
+        session.blend_devices(&[hand]);
+        assert!(is_blended(controller));
+        assert!(is_blended(hand_tracking));
+
+        //User paths which aren't blended never are
+        session.blend_devices(&[controller]);
+        assert!(!is_blended(controller));
+        assert!(!is_blended(hand_tracking));
+    }
+
+    #[test]
+    fn binding_routes() {
+        let left = xr::Path::from_raw(1);
+        let right = xr::Path::from_raw(2);
+        let head = xr::Path::from_raw(3);
+        let subaction_paths = [
+            (left, "/user/hand/left".to_owned()),
+            (right, "/user/hand/right".to_owned()),
+        ];
+        let routes_to = |binding_str: &str, route: Option<xr::Path>, expected: Option<xr::Path>| {
+            assert_eq!(
+                route_binding(binding_str, route, &subaction_paths).map(|path| path.into_raw()),
+                expected.map(|path| path.into_raw()),
+                "{}",
+                binding_str
+            );
+        };
+        let trigger = "/user/hand/left/input/trigger/value";
+
+        routes_to(trigger, None, Some(left));
+        routes_to(trigger, Some(right), Some(right));
+        routes_to(trigger, Some(left), Some(left));
+
+        //Routes to a path the action doesn't have fall back to the binding's own user path
+        routes_to(trigger, Some(head), Some(left));
+        routes_to("/user/head/input/system/click", Some(head), None);
+
+        //Bindings on user paths the action doesn't have are ignored unless routed
+        routes_to("/user/gamepad/input/a/click", None, None);
+        routes_to("/user/gamepad/input/a/click", Some(right), Some(right));
+
+        //Only whole path components match
+        routes_to("/user/hand/leftover/input/a/click", None, None);
+    }
+}
@@ -5,6 +5,10 @@ use common::application_bindings::*;
 use common::serial::read_json;
 use common::serial::get_uuid;
 use common::serial::write_json;
+use crate::binding_modification;
+use crate::eye_gaze;
+use crate::local_floor;
+use crate::vive_tracker;
 use crate::wrappers::*;
 
 use openxr::sys as xr;
@@ -21,27 +25,128 @@ pub unsafe extern "system" fn suggest_interaction_profile_bindings(
 
     let god_set = instance.god_action_sets.get(&(*suggested_bindings).interaction_profile).unwrap();
     println!("Bindings: {}", god_set.name);
+
+    let suggested_modifiers = binding_modification::parse(&instance, &*suggested_bindings);
+
+    //Suggesting bindings for a profile again replaces the previous modifications
+    for action_suggested_binding in action_suggested_bindings {
+        let action = ActionWrapper::from_handle_panic(action_suggested_binding.action);
+        action.suggested_modifiers.write().unwrap().remove(profile_path);
+    }
+
     for action_suggested_binding in action_suggested_bindings {
         let action = ActionWrapper::from_handle_panic(action_suggested_binding.action);
-        let mut action_bindings = action.bindings.write().unwrap();
+        let mut action_bindings = action.suggested_bindings.write().unwrap();
 
         if let Some(bindings) = action_bindings.get_mut(profile_path) {
             bindings.push(action_suggested_binding.binding);
         } else {
             action_bindings.insert(*profile_path, vec![action_suggested_binding.binding]);
         }
+
+        if let Some(modifiers) = suggested_modifiers.get(&(action.handle, action_suggested_binding.binding)) {
+            action.suggested_modifiers.write().unwrap()
+                .entry(*profile_path)
+                .or_default()
+                .insert(instance.path_to_string(action_suggested_binding.binding).unwrap(), modifiers.clone());
+        }
     }
 
     update_default_bindings_file(
         &instance, 
         action_suggested_bindings,
+        &suggested_modifiers,
         &god_set.name
     );
 
     xr::Result::SUCCESS
 }
 
-fn update_default_bindings_file(instance: &InstanceWrapper, suggested_bindings: &[xr::ActionSuggestedBinding], interaction_profile: &str) {
+pub unsafe extern "system" fn poll_event(
+    instance: xr::Instance,
+    event_data: *mut xr::EventDataBuffer
+) -> xr::Result {
+    let instance = InstanceWrapper::from_handle_panic(instance);
+
+    if let Some(event) = instance.pending_events.write().unwrap().pop_front() {
+        *event_data = event.0;
+        return xr::Result::SUCCESS;
+    }
+
+    let result = (instance.core.poll_event)(instance.handle, event_data);
+    if result != xr::Result::SUCCESS {
+        return result;
+    }
+
+    //Roles can also be reassigned or lose their tracker, which the runtime reports as a profile change
+    if (*event_data).ty == vive_tracker::TYPE_EVENT_DATA_VIVE_TRACKER_CONNECTED
+        || (*event_data).ty == xr::StructureType::EVENT_DATA_INTERACTION_PROFILE_CHANGED
+    {
+        if let Err(result) = vive_tracker::refresh_roles(&instance) {
+            println!("failed to enumerate vive trackers: {}", result);
+        }
+    }
+
+    if (*event_data).ty == vive_tracker::TYPE_EVENT_DATA_VIVE_TRACKER_CONNECTED {
+        //The application does not know what this event is
+        if instance.hidden_extensions.contains(vive_tracker::EXTENSION_NAME) {
+            return poll_event(instance.handle, event_data);
+        }
+    }
+
+    if (*event_data).ty == xr::StructureType::EVENT_DATA_REFERENCE_SPACE_CHANGE_PENDING {
+        let event = &*(event_data as *const xr::EventDataReferenceSpaceChangePending);
+        if let Some(event) = local_floor::on_reference_space_change(event) {
+            queue_event(&instance, &event);
+        }
+    }
+
+    result
+}
+
+pub unsafe extern "system" fn get_system_properties(
+    instance: xr::Instance,
+    system_id: xr::SystemId,
+    properties: *mut xr::SystemProperties
+) -> xr::Result {
+    let instance = InstanceWrapper::from_handle_panic(instance);
+
+    let result = (instance.core.get_system_properties)(instance.handle, system_id, properties);
+    if result.into_raw() < 0 {
+        return result;
+    }
+
+    //Head gaze is always available
+    if instance.layer_extension_enabled(eye_gaze::EXTENSION_NAME) {
+        let mut next = (*properties).next as *mut xr::BaseOutStructure;
+        while !next.is_null() {
+            if (*next).ty == xr::StructureType::SYSTEM_EYE_GAZE_INTERACTION_PROPERTIES_EXT {
+                (*(next as *mut xr::SystemEyeGazeInteractionPropertiesEXT)).supports_eye_gaze_interaction = true.into();
+            }
+            next = (*next).next;
+        }
+    }
+
+    result
+}
+
+///Queues an event to be returned by a later xrPollEvent call
+pub fn queue_event<T: Copy>(instance: &InstanceWrapper, event: &T) {
+    assert!(std::mem::size_of::<T>() <= std::mem::size_of::<xr::EventDataBuffer>());
+    unsafe {
+        let mut buffer = std::mem::zeroed::<xr::EventDataBuffer>();
+        *(&mut buffer as *mut xr::EventDataBuffer as *mut T) = *event;
+        buffer.next = std::ptr::null();
+        instance.pending_events.write().unwrap().push_back(PendingEvent(buffer));
+    }
+}
+
+fn update_default_bindings_file(
+    instance: &InstanceWrapper,
+    suggested_bindings: &[xr::ActionSuggestedBinding],
+    suggested_modifiers: &binding_modification::SuggestedModifiers,
+    interaction_profile: &str,
+) {
     let file_path = format!("{}{}/default_bindings.json", CONFIG_DIR, get_uuid(&instance.application_name));
 
     println!("{}", file_path);
@@ -68,14 +173,11 @@ fn update_default_bindings_file(instance: &InstanceWrapper, suggested_bindings:
             },
         };
 
-        match action_set.actions.get_mut(&action.name) {
-            Some(action) => action.bindings.push(binding_string),
-            None => {
-                action_set.actions.insert(action.name.clone(), ActionBindings {
-                    bindings: vec![binding_string],
-                });
-            },
-        }        
+        let action_bindings = action_set.actions.entry(action.name.clone()).or_default();
+        if let Some(modifiers) = suggested_modifiers.get(&(action.handle, suggested_binding.binding)) {
+            action_bindings.modifiers.insert(binding_string.clone(), modifiers.clone());
+        }
+        action_bindings.bindings.push(binding_string);
     }
 
     default_bindings.profiles.insert(interaction_profile.to_owned(), profile);
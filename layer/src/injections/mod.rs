@@ -8,6 +8,8 @@ use std::sync::Arc;
 use std::sync::RwLock;
 
 use crate::i8_arr_to_owned;
+use crate::local_floor;
+use crate::space_drag;
 use crate::wrappers::*;
 
 use common::xrapplication_info::ActionType;
@@ -34,6 +36,12 @@ pub unsafe extern "system" fn create_session(
         }
     };
 
+    if let Err(result) =
+        space_drag::configure(&wrapper, instance.settings.read().unwrap().space_drag.as_ref())
+    {
+        println!("failed to set up space drag: {}", result);
+    }
+
     //Add this session to the wrapper tree
     instance.sessions.write().unwrap().push(wrapper.clone());
 
@@ -102,7 +110,10 @@ pub unsafe extern "system" fn create_action(
         )
         .to_owned(),
         localized_name: i8_arr_to_owned(&create_info.localized_action_name),
+        suggested_bindings: Default::default(),
+        suggested_modifiers: Default::default(),
         bindings: Default::default(),
+        binding_routes: Default::default(),
     });
 
     //Add this action to the wrapper tree
@@ -156,6 +167,7 @@ pub unsafe extern "system" fn create_action_space(
         sync_idx: RwLock::new(0),
 
         cur_binding: RwLock::new(None),
+        filter_states: Default::default(),
     });
 
     let wrapper = Arc::new(SpaceWrapper {
@@ -192,16 +204,35 @@ pub unsafe extern "system" fn create_reference_space(
         None => return xr::Result::ERROR_HANDLE_INVALID,
     };
 
-    let result =
-        (session.instance().core.create_reference_space)(session.handle, create_info, handle);
+    let emulate_local_floor = (*create_info).reference_space_type
+        == local_floor::REFERENCE_SPACE_TYPE_LOCAL_FLOOR
+        && session.instance().layer_extension_enabled(local_floor::EXTENSION_NAME);
+
+    let result = if emulate_local_floor {
+        //The handle given to the application is never used by the layer
+        let mut create_info = *create_info;
+        create_info.reference_space_type = xr::ReferenceSpaceType::LOCAL;
+        (session.instance().core.create_reference_space)(session.handle, &create_info, handle)
+    } else {
+        (session.instance().core.create_reference_space)(session.handle, create_info, handle)
+    };
     if result.into_raw() < 0 {
         return result;
     }
 
+    if emulate_local_floor {
+        *session.local_floor_dirty.write().unwrap() = true;
+    }
+
+    let reference_space = Arc::new(ReferenceSpace {
+        reference_space_type: (*create_info).reference_space_type,
+        pose_in_reference_space: (*create_info).pose_in_reference_space,
+    });
+
     let wrapper = Arc::new(SpaceWrapper {
         unchecked_handle: *handle,
         session: Arc::downgrade(&session),
-        ty: SpaceType::REFERENCE,
+        ty: SpaceType::REFERENCE(reference_space),
     });
 
     //Add this space to the wrapper tree
@@ -339,6 +370,7 @@ fn destroy_instance_internal(handle: xr::Instance) {
 
 fn destroy_session_internal(handle: xr::Session) -> Arc<SessionWrapper> {
     let session = sessions().remove(&handle).unwrap().1;
+    crate::ipc::session_destroyed(handle);
 
     println!("Destroyed {:?}", handle);
 
@@ -1,16 +1,36 @@
 use core::slice;
+use std::os::raw::c_char;
 use std::collections::HashMap;
 use std::ops::Deref;
 use std::path::Path;
 use std::ptr;
 use std::sync::{Arc, RwLock, Weak};
+use std::time::{Duration, Instant, SystemTime};
 
-use crate::god_actions::{self, Binding, CachedActionStatesEnum, SubactionBindings};
+use crate::god_actions::{self, Binding, CachedActionStatesEnum, SubactionBindings, UserBindings};
 use crate::path::*;
+use crate::capture;
+use crate::eye_gaze;
+use crate::gamepad;
+use crate::input_names;
+use crate::ipc;
+use crate::local_floor;
+use crate::math;
+use crate::oxidexr_ext;
+use crate::space_drag;
 use crate::validation::Validate;
+use crate::util;
 use crate::wrappers::*;
+use common::application_bindings::ActionBindings;
+use common::application_bindings::ApplicationBindings;
+use common::application_bindings::HapticRoute;
+use common::application_bindings::HapticRules;
+use common::application_settings::ApplicationSettings;
+use common::ipc::Event;
+use common::capture::{capture_path, CaptureRequest};
 use common::serial::get_uuid;
 use common::serial::read_json;
+use common::serial::try_read_json;
 use common::serial::write_json;
 use common::serial::CONFIG_DIR;
 use common::xrapplication_info::*;
@@ -33,9 +53,12 @@ pub unsafe extern "system" fn attach_session_action_sets(
         (*attach_info).count_action_sets as usize,
     );
 
+    let application_bindings = read_application_bindings(&instance);
+
     let mut input_bindings_sets = HashMap::new();
     let mut cached_action_states = HashMap::new();
     let mut output_bindings = HashMap::new();
+    let mut haptic_rules = HashMap::new();
 
     for action_set in action_sets {
         let action_set = match action_set.get_wrapper() {
@@ -46,6 +69,8 @@ pub unsafe extern "system" fn attach_session_action_sets(
         let mut input_bindings = HashMap::new();
 
         for action in action_set.actions.read().unwrap().iter() {
+            apply_user_bindings(&instance, &session, &application_bindings, &action_set, &action);
+
             let bindings = action
                 .bindings
                 .read()
@@ -75,6 +100,7 @@ pub unsafe extern "system" fn attach_session_action_sets(
                     RwLock::new(CachedActionStatesEnum::new(
                         action.action_type,
                         &action.subaction_paths,
+                        get_user_bindings(&instance, &application_bindings, &action_set, &action),
                     )),
                 );
 
@@ -94,6 +120,15 @@ pub unsafe extern "system" fn attach_session_action_sets(
                         &session.god_outputs,
                     )),
                 );
+                haptic_rules.insert(
+                    action.handle,
+                    RwLock::new(get_haptic_rules(get_user_bindings(
+                        &instance,
+                        &application_bindings,
+                        &action_set,
+                        &action,
+                    ))),
+                );
 
                 for (profile_name, bindings) in action.bindings.read().unwrap().iter() {
                     println!(" {}", instance.path_to_string(*profile_name).unwrap());
@@ -116,6 +151,12 @@ pub unsafe extern "system" fn attach_session_action_sets(
     if let Err(_) = session.output_bindings.set(output_bindings) {
         return xr::Result::ERROR_ACTIONSETS_ALREADY_ATTACHED;
     }
+    if let Err(_) = session.haptic_rules.set(haptic_rules) {
+        return xr::Result::ERROR_ACTIONSETS_ALREADY_ATTACHED;
+    }
+
+    *session.user_bindings_modified.write().unwrap() =
+        (get_modified_time(&bindings_path(&instance)), Some(Instant::now()));
 
     update_application_actions(&session.instance(), &action_sets);
 
@@ -136,6 +177,7 @@ pub unsafe extern "system" fn sync_actions(
         let god_sets = instance
             .god_action_sets
             .values()
+            .filter(|god_set| god_set.handle != xr::ActionSet::NULL)
             .map(|god_set| xr::ActiveActionSet {
                 action_set: god_set.handle,
                 subaction_path: xr::Path::NULL,
@@ -154,7 +196,23 @@ pub unsafe extern "system" fn sync_actions(
     }
 
     //Update the active profile for each user path TODO: listen to XR_TYPE_EVENT_DATA_INTERACTION_PROFILE_CHANGED
+    let emulated_user_path = if instance.layer_extension_enabled(eye_gaze::EXTENSION_NAME) {
+        instance.string_to_path(eye_gaze::USER_PATH).ok()
+    } else {
+        None
+    };
     for (user_path, active_profile) in &session.active_profiles {
+        //The emulated eye gaze profile never changes
+        if Some(user_path.0) == emulated_user_path {
+            continue;
+        }
+
+        //Roles without a tracker have no profile
+        if instance.vive_tracker_roles.read().unwrap().get(&user_path.0) == Some(&false) {
+            *active_profile.write().unwrap() = InteractionProfilePath(xr::Path::NULL);
+            continue;
+        }
+
         let mut profile_state = xr::InteractionProfileState {
             ty: xr::InteractionProfileState::TYPE,
             next: ptr::null_mut(),
@@ -174,6 +232,10 @@ pub unsafe extern "system" fn sync_actions(
         *active_profile.write().unwrap() =
             InteractionProfilePath(profile_state.interaction_profile);
     }
+    gamepad::update_active_profile(&session);
+
+    reload_capture_request(&session);
+    let capturing = capture::is_armed(&session);
 
     for god_state in session
         .god_states
@@ -182,10 +244,31 @@ pub unsafe extern "system" fn sync_actions(
         .flatten()
     {
         //Check if the state has more than one reference since states with only one reference are not being used
-        if Arc::strong_count(god_state) > 1 {
+        //While capturing every input is needed
+        if Arc::strong_count(god_state) > 1 || capturing {
             god_state.sync(&session).unwrap();
         }
     }
+    session.update_blended_devices();
+
+    if capturing {
+        let from_file = capture::is_from_file(&session);
+        if let Some(result) = capture::update(&session, &bindings_path(&instance)) {
+            if from_file {
+                let _ = std::fs::remove_file(capture_path(&get_uuid(&instance.application_name)));
+            }
+            let (binding, error) = match result {
+                capture::CaptureResult::Captured(binding) => (Some(binding), None),
+                capture::CaptureResult::TimedOut => (None, None),
+                capture::CaptureResult::Failed(error) => (None, Some(error)),
+            };
+            ipc::broadcast(Event::CaptureFinished {
+                session: session.handle.into_raw(),
+                binding,
+                error,
+            });
+        }
+    }
 
     let sync_idx = {
         let mut sync_idx = session.sync_idx.write().unwrap();
@@ -193,6 +276,25 @@ pub unsafe extern "system" fn sync_actions(
         *sync_idx
     };
 
+    //Modifiers advance on the predicted display time of the application's latest frame, a second sync in the same frame doesn't move them
+    let (time, delta) = {
+        let time = *session.predicted_display_time.read().unwrap();
+        let mut last_sync_time = session.last_sync_time.write().unwrap();
+        let delta = match *last_sync_time {
+            Some(last_time) => (time.as_nanos() - last_time.as_nanos()).max(0) as f32 / 1e9,
+            None => 0f32,
+        };
+        *last_sync_time = Some(time);
+        (time, delta)
+    };
+
+    reload_user_bindings(&session);
+    reload_settings(&session);
+
+    if let Err(result) = space_drag::update(&session, time) {
+        println!("failed to drag playspace: {}", result);
+    }
+
     let active_action_sets = std::slice::from_raw_parts(
         (*app_sync_info).active_action_sets,
         (*app_sync_info).count_active_action_sets as usize,
@@ -217,16 +319,32 @@ pub unsafe extern "system" fn sync_actions(
 
             let subaction_bindings = subaction_bindings.read().unwrap();
 
-            if let Err(result) = action_cache_states.sync(&subaction_bindings) {
+            if let Err(result) = action_cache_states.sync(&subaction_bindings, time, delta, &|profile, user_path| {
+                session.is_device_active(profile, user_path)
+            }) {
                 return result;
             }
 
+            if let Err(result) = play_haptic_triggers(
+                &session,
+                &subaction_bindings,
+                action_cache_states.user_bindings(),
+                action_cache_states.modifier_states(),
+                time,
+                delta,
+            ) {
+                println!("failed to play haptic trigger: {}", result);
+            }
+
             if let god_actions::CachedActionStatesEnum::Pose(_) = action_cache_states.deref() {
                 if let Some(action_spaces) = session.action_spaces.get_mut(action_handle) {
                     for action_space in action_spaces.iter() {
-                        if let Err(result) =
-                            action_space.sync(&session, sync_idx, &subaction_bindings)
-                        {
+                        if let Err(result) = action_space.sync(
+                            &session,
+                            sync_idx,
+                            &subaction_bindings,
+                            action_cache_states.user_bindings(),
+                        ) {
                             return result;
                         }
                     }
@@ -235,9 +353,317 @@ pub unsafe extern "system" fn sync_actions(
         }
     }
 
+    ipc::publish_snapshot(&session, sync_idx);
+
     result
 }
 
+///Plays the haptic pulses the user has attached to the boolean bindings of an action
+fn play_haptic_triggers(
+    session: &SessionWrapper,
+    subaction_bindings: &SubactionBindings<god_actions::InputBinding>,
+    user_bindings: &UserBindings,
+    modifier_states: &god_actions::ModifierStates,
+    time: xr::Time,
+    delta: f32,
+) -> Result<()> {
+    let instance = session.instance();
+
+    for binding in subaction_bindings.get_matching(xr::Path::NULL)? {
+        let modifiers = match user_bindings
+            .get(&binding.action.profile_name)
+            .and_then(|action_bindings| action_bindings.modifiers.get(&binding.binding_str))
+        {
+            Some(modifiers) if !modifiers.haptic_triggers.is_empty() => modifiers,
+            _ => continue,
+        };
+
+        let modifier_state = modifier_states
+            .get(&binding.action.profile_name)
+            .and_then(|states| states.get(&binding.binding_str));
+        let state = match god_actions::get_pressed_state(&binding.action_state.read().unwrap(), modifiers, modifier_state) {
+            Some(state) => state,
+            None => continue,
+        };
+
+        for trigger in &modifiers.haptic_triggers {
+            if !god_actions::is_triggered(trigger.condition, &state, time, delta) {
+                continue;
+            }
+
+            let output_str = match &trigger.output {
+                Some(output_str) => output_str.clone(),
+                None => instance.path_to_string(binding.subaction_path)? + "/output/haptic",
+            };
+
+            let output = match instance.string_to_path(&output_str).ok().and_then(|path| {
+                session
+                    .god_outputs
+                    .get(&binding.action.profile_name)
+                    .and_then(|outputs| outputs.get(&path))
+            }) {
+                Some(output) => output,
+                None => {
+                    println!("No haptic output {} for {}", output_str, binding.binding_str);
+                    continue;
+                }
+            };
+
+            if !output.is_active(session) {
+                continue;
+            }
+
+            let vibration = xr::HapticVibration {
+                ty: xr::HapticVibration::TYPE,
+                next: ptr::null(),
+                duration: xr::Duration::from_nanos((trigger.duration as f64 * 1e9) as i64),
+                frequency: trigger.frequency,
+                amplitude: trigger.amplitude,
+            };
+
+            session.apply_haptic_feedback(
+                &xr::HapticActionInfo {
+                    ty: xr::HapticActionInfo::TYPE,
+                    next: ptr::null(),
+                    action: output.action.handle,
+                    subaction_path: output.subaction_path,
+                },
+                &vibration as *const xr::HapticVibration as *const xr::HapticBaseHeader,
+            )?;
+        }
+    }
+
+    Ok(())
+}
+
+pub unsafe extern "system" fn wait_frame(
+    session: xr::Session,
+    frame_wait_info: *const xr::FrameWaitInfo,
+    frame_state: *mut xr::FrameState,
+) -> xr::Result {
+    let session = match session.get_wrapper() {
+        Some(session) => session,
+        None => return xr::Result::ERROR_HANDLE_INVALID,
+    };
+
+    let result = session.wait_frame(frame_wait_info, frame_state);
+    if result.into_raw() < 0 {
+        return result;
+    }
+
+    *session.predicted_display_time.write().unwrap() = (*frame_state).predicted_display_time;
+
+    if session.instance().layer_extension_enabled(local_floor::EXTENSION_NAME) {
+        if let Err(result) = local_floor::update(&session, (*frame_state).predicted_display_time) {
+            println!("failed to update local floor: {}", result);
+        }
+    }
+
+    result
+}
+
+pub unsafe extern "system" fn get_current_interaction_profile(
+    session: xr::Session,
+    top_level_user_path: xr::Path,
+    interaction_profile: *mut xr::InteractionProfileState,
+) -> xr::Result {
+    let session = match session.get_wrapper() {
+        Some(session) => session,
+        None => return xr::Result::ERROR_HANDLE_INVALID,
+    };
+    let instance = session.instance();
+
+    //Emulated devices are only known to the layer
+    if let Some(active_profile) = session.active_profiles.get(&TopLevelUserPath(top_level_user_path)) {
+        let emulated = match instance.path_to_string(top_level_user_path).as_deref() {
+            Ok(eye_gaze::USER_PATH) => instance.layer_extension_enabled(eye_gaze::EXTENSION_NAME),
+            Ok(gamepad::USER_PATH) => gamepad::is_enabled(&instance),
+            _ => false,
+        };
+        if emulated {
+            (*interaction_profile).interaction_profile = active_profile.read().unwrap().0;
+            return xr::Result::SUCCESS;
+        }
+    }
+
+    (instance.core.get_current_interaction_profile)(
+        session.handle,
+        top_level_user_path,
+        interaction_profile,
+    )
+}
+
+pub unsafe extern "system" fn enumerate_reference_spaces(
+    session: xr::Session,
+    space_capacity_input: u32,
+    space_count_output: *mut u32,
+    spaces: *mut xr::ReferenceSpaceType,
+) -> xr::Result {
+    let session = match session.get_wrapper() {
+        Some(session) => session,
+        None => return xr::Result::ERROR_HANDLE_INVALID,
+    };
+    let instance = session.instance();
+
+    let result = (instance.core.enumerate_reference_spaces)(
+        session.handle,
+        space_capacity_input,
+        space_count_output,
+        spaces,
+    );
+    if !instance.layer_extension_enabled(local_floor::EXTENSION_NAME) {
+        return result;
+    }
+    if result == xr::Result::ERROR_SIZE_INSUFFICIENT {
+        *space_count_output += 1;
+        return result;
+    }
+    if result.into_raw() < 0 {
+        return result;
+    }
+
+    //Emulated spaces go on the end of the runtime's list
+    let count = *space_count_output;
+    *space_count_output = count + 1;
+    if space_capacity_input != 0 {
+        if space_capacity_input < count + 1 {
+            return xr::Result::ERROR_SIZE_INSUFFICIENT;
+        }
+        *spaces.add(count as usize) = local_floor::REFERENCE_SPACE_TYPE_LOCAL_FLOOR;
+    }
+
+    result
+}
+
+pub unsafe extern "system" fn end_frame(
+    session: xr::Session,
+    frame_end_info: *const xr::FrameEndInfo,
+) -> xr::Result {
+    let session = match session.get_wrapper() {
+        Some(session) => session,
+        None => return xr::Result::ERROR_HANDLE_INVALID,
+    };
+
+    let layers: &[*const xr::CompositionLayerBaseHeader] = if (*frame_end_info).layer_count == 0 {
+        &[]
+    } else {
+        std::slice::from_raw_parts(
+            (*frame_end_info).layers,
+            (*frame_end_info).layer_count as usize,
+        )
+    };
+
+    //Layers are copied so their spaces can be replaced with the ones the layer actually uses
+    //The vecs never grow past their capacity so the pointers into them stay valid
+    let mut projections = Vec::with_capacity(layers.len());
+    let mut projection_views = Vec::with_capacity(layers.len());
+    let mut quads = Vec::with_capacity(layers.len());
+    let mut cylinders = Vec::with_capacity(layers.len());
+    let mut equirects = Vec::with_capacity(layers.len());
+    let mut equirect2s = Vec::with_capacity(layers.len());
+    let mut cubes = Vec::with_capacity(layers.len());
+    let mut my_layers = Vec::with_capacity(layers.len());
+
+    for layer in layers {
+        let layer = *layer;
+        my_layers.push(match (*layer).ty {
+            xr::StructureType::COMPOSITION_LAYER_PROJECTION => {
+                copy_layer(layer, &mut projections, |projection: &mut xr::CompositionLayerProjection| {
+                    let (space, correction) = get_space(projection.space);
+                    projection.space = space;
+                    if let Some(correction) = correction {
+                        let mut views = slice::from_raw_parts(projection.views, projection.view_count as usize).to_vec();
+                        for view in &mut views {
+                            view.pose = math::pose_mul(&correction, &view.pose);
+                        }
+                        //Moving the vec doesn't move its buffer
+                        projection.views = views.as_ptr();
+                        projection_views.push(views);
+                    }
+                })
+            }
+            xr::StructureType::COMPOSITION_LAYER_QUAD => {
+                copy_layer(layer, &mut quads, |quad: &mut xr::CompositionLayerQuad| {
+                    let (space, correction) = get_space(quad.space);
+                    quad.space = space;
+                    quad.pose = correct_pose(correction, quad.pose);
+                })
+            }
+            xr::StructureType::COMPOSITION_LAYER_CYLINDER_KHR => {
+                copy_layer(layer, &mut cylinders, |cylinder: &mut xr::CompositionLayerCylinderKHR| {
+                    let (space, correction) = get_space(cylinder.space);
+                    cylinder.space = space;
+                    cylinder.pose = correct_pose(correction, cylinder.pose);
+                })
+            }
+            xr::StructureType::COMPOSITION_LAYER_EQUIRECT_KHR => {
+                copy_layer(layer, &mut equirects, |equirect: &mut xr::CompositionLayerEquirectKHR| {
+                    let (space, correction) = get_space(equirect.space);
+                    equirect.space = space;
+                    equirect.pose = correct_pose(correction, equirect.pose);
+                })
+            }
+            xr::StructureType::COMPOSITION_LAYER_EQUIRECT2_KHR => {
+                copy_layer(layer, &mut equirect2s, |equirect: &mut xr::CompositionLayerEquirect2KHR| {
+                    let (space, correction) = get_space(equirect.space);
+                    equirect.space = space;
+                    equirect.pose = correct_pose(correction, equirect.pose);
+                })
+            }
+            xr::StructureType::COMPOSITION_LAYER_CUBE_KHR => {
+                copy_layer(layer, &mut cubes, |cube: &mut xr::CompositionLayerCubeKHR| {
+                    let (space, correction) = get_space(cube.space);
+                    cube.space = space;
+                    //Cubes only have an orientation
+                    cube.orientation = correct_pose(
+                        correction,
+                        xr::Posef {
+                            orientation: cube.orientation,
+                            position: Default::default(),
+                        },
+                    )
+                    .orientation;
+                })
+            }
+            //The layer doesn't know where this one keeps its space and pose
+            _ => layer,
+        });
+    }
+
+    let mut my_frame_end_info = *frame_end_info;
+    my_frame_end_info.layers = my_layers.as_ptr();
+
+    session.end_frame(&my_frame_end_info)
+}
+
+///Copies a layer so it can be changed, the copy is what the runtime is given
+unsafe fn copy_layer<T: Copy, F: FnOnce(&mut T)>(
+    layer: *const xr::CompositionLayerBaseHeader,
+    copies: &mut Vec<T>,
+    change: F,
+) -> *const xr::CompositionLayerBaseHeader {
+    let mut copy = *(layer as *const T);
+    change(&mut copy);
+    copies.push(copy);
+    copies.last().unwrap() as *const T as *const xr::CompositionLayerBaseHeader
+}
+
+///The handle the runtime should see in place of one given to the application and the pose of the application's space within it
+fn get_space(space: xr::Space) -> (xr::Space, Option<xr::Posef>) {
+    match space.get_wrapper() {
+        Some(wrapper) => (wrapper.get_handle().unwrap_or(space), wrapper.correction()),
+        None => (space, None),
+    }
+}
+
+///A pose in the application's space made relative to the runtime's
+fn correct_pose(correction: Option<xr::Posef>, pose: xr::Posef) -> xr::Posef {
+    match correction {
+        Some(correction) => math::pose_mul(&correction, &pose),
+        None => pose,
+    }
+}
+
 pub unsafe extern "system" fn get_action_state_boolean(
     session: xr::Session,
     get_info: *const xr::ActionStateGetInfo,
@@ -490,14 +916,27 @@ pub unsafe extern "system" fn locate_views(
     let mut my_view_locate_info = *view_locate_info;
     my_view_locate_info.space = space_handle;
 
-    (session.instance().core.locate_views)(
+    let result = (session.instance().core.locate_views)(
         session.handle,
         &my_view_locate_info,
         view_state,
         view_capacity_input,
         view_count_output,
         views,
-    )
+    );
+
+    if result.into_raw() < 0 || view_capacity_input == 0 {
+        return result;
+    }
+
+    if let Some(correction) = space.correction() {
+        let correction = math::pose_inverse(&correction);
+        for view in slice::from_raw_parts_mut(views, *view_count_output as usize) {
+            view.pose = math::pose_mul(&correction, &view.pose);
+        }
+    }
+
+    result
 }
 
 pub unsafe extern "system" fn apply_haptic_feedback(
@@ -508,8 +947,19 @@ pub unsafe extern "system" fn apply_haptic_feedback(
     match for_each_output_binding(
         session,
         &*haptic_action_info,
-        |session, info| -> Result<xr::Result> {
-            session.apply_haptic_feedback(&info, haptic_feedback)
+        |session, info, rules| -> Result<xr::Result> {
+            match rules {
+                Some(rules) if rules.mute => Ok(xr::Result::SUCCESS),
+                Some(rules) if (*haptic_feedback).ty == xr::HapticVibration::TYPE => {
+                    let mut vibration = *(haptic_feedback as *const xr::HapticVibration);
+                    shape_vibration(rules, &mut vibration);
+                    session.apply_haptic_feedback(
+                        &info,
+                        &vibration as *const xr::HapticVibration as *const xr::HapticBaseHeader,
+                    )
+                }
+                _ => session.apply_haptic_feedback(&info, haptic_feedback),
+            }
         },
     ) {
         Ok(result) => result,
@@ -524,7 +974,7 @@ pub unsafe extern "system" fn stop_haptic_feedback(
     match for_each_output_binding(
         session,
         &*haptic_action_info,
-        |session, info| -> Result<xr::Result> { session.stop_haptic_feedback(&info) },
+        |session, info, _| -> Result<xr::Result> { session.stop_haptic_feedback(&info) },
     ) {
         Ok(result) => result,
         Err(result) => result,
@@ -537,7 +987,7 @@ fn for_each_output_binding<F>(
     callback: F,
 ) -> Result<xr::Result>
 where
-    F: Fn(&SessionWrapper, xr::HapticActionInfo) -> Result<xr::Result>,
+    F: Fn(&SessionWrapper, xr::HapticActionInfo, Option<&HapticRules>) -> Result<xr::Result>,
 {
     let session = session.try_get_wrapper()?;
     let action = haptic_action_info.action.try_get_wrapper()?;
@@ -549,6 +999,16 @@ where
     .read()
     .unwrap();
 
+    let haptic_rules = session
+        .haptic_rules
+        .get()
+        .unwrap()
+        .get(&action.handle)
+        .map(|rules| rules.read().unwrap().clone());
+
+    //Routing can send several bindings to the same device so each target is only called once
+    let mut targets = Vec::new();
+
     for binding in subaction_bindings
         .get_matching(haptic_action_info.subaction_path)
         .unwrap()
@@ -557,15 +1017,75 @@ where
     {
         println!("{}", binding.action.profile_name_str);
 
+        let rules = haptic_rules
+            .as_ref()
+            .and_then(|rules| rules.get(&binding.action.profile_name));
+
+        let subaction_paths = match rules.and_then(|rules| rules.route).unwrap_or_default() {
+            HapticRoute::Same => vec![binding.subaction_path],
+            HapticRoute::Swap => {
+                let others = binding
+                    .action
+                    .subaction_paths
+                    .iter()
+                    .copied()
+                    .filter(|subaction_path| *subaction_path != binding.subaction_path)
+                    .collect::<Vec<_>>();
+                if others.is_empty() {
+                    vec![binding.subaction_path]
+                } else {
+                    others
+                }
+            }
+            HapticRoute::Both => binding.action.subaction_paths.clone(),
+        };
+
+        for subaction_path in subaction_paths {
+            if !targets
+                .iter()
+                .any(|(action, path, _)| *action == binding.action.handle && *path == subaction_path)
+            {
+                targets.push((binding.action.handle, subaction_path, rules));
+            }
+        }
+    }
+
+    for (god_action, subaction_path, rules) in targets {
         let mut my_haptic_action_info = *haptic_action_info;
-        my_haptic_action_info.action = binding.action.handle;
+        my_haptic_action_info.action = god_action;
+        my_haptic_action_info.subaction_path = subaction_path;
 
-        callback(&session, my_haptic_action_info)?;
+        callback(&session, my_haptic_action_info, rules)?;
     }
 
     Ok(xr::Result::SUCCESS)
 }
 
+fn shape_vibration(rules: &HapticRules, vibration: &mut xr::HapticVibration) {
+    if let Some(amplitude_scale) = rules.amplitude_scale {
+        vibration.amplitude = (vibration.amplitude * amplitude_scale).max(0f32).min(1f32);
+    }
+
+    //A negative duration is XR_MIN_HAPTIC_DURATION which is left to the runtime unless the user has set a minimum
+    let mut duration = vibration.duration.as_nanos();
+    if duration >= 0 {
+        if let Some(duration_scale) = rules.duration_scale {
+            duration = (duration as f64 * duration_scale as f64) as i64;
+        }
+        if let Some(max_duration) = rules.max_duration {
+            duration = duration.min((max_duration as f64 * 1e9) as i64);
+        }
+    }
+    if let Some(min_duration) = rules.min_duration {
+        duration = duration.max((min_duration as f64 * 1e9) as i64);
+    }
+    vibration.duration = xr::Duration::from_nanos(duration);
+
+    if let Some(frequency) = rules.frequency {
+        vibration.frequency = frequency;
+    }
+}
+
 fn update_application_actions(instance: &InstanceWrapper, action_set_handles: &[xr::ActionSet]) {
     let path_str = format!(
         "{}{}/actions.json",
@@ -595,6 +1115,349 @@ fn update_application_actions(instance: &InstanceWrapper, action_set_handles: &[
     write_json(&application_actions, &Path::new(&path_str));
 }
 
+pub fn bindings_path(instance: &InstanceWrapper) -> String {
+    format!(
+        "{}{}/bindings.json",
+        CONFIG_DIR,
+        get_uuid(&instance.application_name)
+    )
+}
+
+fn read_application_bindings(instance: &InstanceWrapper) -> ApplicationBindings {
+    match read_json(&bindings_path(instance)) {
+        Some(application_bindings) => application_bindings,
+        None => ApplicationBindings::default(),
+    }
+}
+
+fn get_modified_time(path_str: &str) -> Option<SystemTime> {
+    std::fs::metadata(path_str)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
+
+///Checks if a config file has been modified, at most once a second, returning its new modified time
+///The time is only recorded by file_read so a file which is caught mid-write and fails to parse is read again
+fn file_changed(path_str: &str, modified: &RwLock<(Option<SystemTime>, Option<Instant>)>) -> Option<Option<SystemTime>> {
+    let mut modified = modified.write().unwrap();
+    let (last_modified, last_checked) = *modified;
+
+    if let Some(last_checked) = last_checked {
+        if last_checked.elapsed() < Duration::from_secs(1) {
+            return None;
+        }
+    }
+
+    let new_modified = get_modified_time(path_str);
+    modified.1 = Some(Instant::now());
+
+    //The first check only records the time
+    if last_checked.is_none() {
+        modified.0 = new_modified;
+        return None;
+    }
+
+    if new_modified != last_modified {
+        Some(new_modified)
+    } else {
+        None
+    }
+}
+
+///Records that a config file has been read as of the time returned by file_changed
+fn file_read(modified: &RwLock<(Option<SystemTime>, Option<Instant>)>, new_modified: Option<SystemTime>) {
+    modified.write().unwrap().0 = new_modified;
+}
+
+///Arms a capture when the editor writes a request
+fn reload_capture_request(session: &SessionWrapper) {
+    let instance = session.instance();
+
+    let path_str = capture_path(&get_uuid(&instance.application_name));
+    let modified = match file_changed(&path_str, &session.capture_modified) {
+        Some(modified) => modified,
+        None => return,
+    };
+
+    if !Path::new(&path_str).exists() {
+        file_read(&session.capture_modified, modified);
+        //Captures armed over IPC don't use the file
+        capture::cancel_from_file(session);
+        return;
+    }
+    let request = match try_read_json::<CaptureRequest>(&path_str) {
+        Some(request) => request,
+        None => return,
+    };
+    file_read(&session.capture_modified, modified);
+    if let Err(err) = capture::arm(session, request, true) {
+        println!("Can't capture: {}", err);
+        let _ = std::fs::remove_file(&path_str);
+    }
+}
+
+///Reloads the application's settings if the settings file has changed
+fn reload_settings(session: &SessionWrapper) {
+    let instance = session.instance();
+
+    let path_str = instance.settings_path();
+    let modified = match file_changed(&path_str, &session.settings_modified) {
+        Some(modified) => modified,
+        None => return,
+    };
+
+    let settings = if Path::new(&path_str).exists() {
+        match try_read_json::<ApplicationSettings>(&path_str) {
+            Some(settings) => settings,
+            None => return,
+        }
+    } else {
+        ApplicationSettings::default()
+    };
+    file_read(&session.settings_modified, modified);
+
+    println!("Reloading settings");
+
+    let playspace = settings.playspace.unwrap_or_default();
+    if settings.local_floor_height != instance.settings.read().unwrap().local_floor_height {
+        *session.local_floor_dirty.write().unwrap() = true;
+    }
+    let space_drag = settings.space_drag.clone();
+    let space_drag_changed = space_drag != instance.settings.read().unwrap().space_drag;
+    *instance.settings.write().unwrap() = settings;
+
+    if space_drag_changed {
+        if let Err(result) = space_drag::configure(session, space_drag.as_ref()) {
+            println!("failed to set up space drag: {}", result);
+        }
+    }
+
+    if playspace != *session.playspace_offset.read().unwrap() {
+        session.set_playspace_offset(playspace);
+    }
+}
+
+///Re-applies the user's bindings to every attached action if the bindings file has changed (checked at most once a second)
+fn reload_user_bindings(session: &SessionWrapper) {
+    let instance = session.instance();
+
+    let path_str = bindings_path(&instance);
+    let modified = match file_changed(&path_str, &session.user_bindings_modified) {
+        Some(modified) => modified,
+        None => return,
+    };
+
+    //The file may be mid-write so a bad read is tried again on the next check
+    let application_bindings = match reread_application_bindings(&path_str) {
+        Some(application_bindings) => application_bindings,
+        None => return,
+    };
+    file_read(&session.user_bindings_modified, modified);
+
+    println!("Reloading user bindings");
+
+    let input_bindings = session.input_bindings.get().unwrap();
+    let cached_action_states = session.cached_action_states.get().unwrap();
+    for (action_set, actions) in input_bindings {
+        let action_set = match action_set.get_wrapper() {
+            Some(action_set) => action_set,
+            None => continue,
+        };
+        for (action, subaction_bindings) in actions {
+            let action = match action.get_wrapper() {
+                Some(action) => action,
+                None => continue,
+            };
+
+            apply_user_bindings(&instance, session, &application_bindings, &action_set, &action);
+            *subaction_bindings.write().unwrap() =
+                SubactionBindings::new(&instance, &action, &session.god_states);
+            cached_action_states
+                .get(&action.handle)
+                .unwrap()
+                .write()
+                .unwrap()
+                .set_user_bindings(get_user_bindings(
+                    &instance,
+                    &application_bindings,
+                    &action_set,
+                    &action,
+                ));
+        }
+    }
+
+    let haptic_rules = session.haptic_rules.get().unwrap();
+    for (action, subaction_bindings) in session.output_bindings.get().unwrap() {
+        let action = match action.get_wrapper() {
+            Some(action) => action,
+            None => continue,
+        };
+        let action_set = match action.action_set.upgrade() {
+            Some(action_set) => action_set,
+            None => continue,
+        };
+
+        apply_user_bindings(&instance, session, &application_bindings, &action_set, &action);
+        *subaction_bindings.write().unwrap() =
+            SubactionBindings::new(&instance, &action, &session.god_outputs);
+        *haptic_rules.get(&action.handle).unwrap().write().unwrap() = get_haptic_rules(
+            get_user_bindings(&instance, &application_bindings, &action_set, &action),
+        );
+    }
+
+    oxidexr_ext::bindings_changed(session);
+    ipc::broadcast(Event::BindingsChanged {
+        session: session.handle.into_raw(),
+    });
+}
+
+fn reread_application_bindings(path_str: &str) -> Option<ApplicationBindings> {
+    if Path::new(path_str).exists() {
+        try_read_json(path_str)
+    } else {
+        Some(ApplicationBindings::default())
+    }
+}
+
+///Replaces the application's suggested bindings with the user's bindings for every profile they have edited
+fn apply_user_bindings(
+    instance: &InstanceWrapper,
+    session: &SessionWrapper,
+    application_bindings: &ApplicationBindings,
+    action_set: &ActionSetWrapper,
+    action: &ActionWrapper,
+) {
+    *action.bindings.write().unwrap() = action.suggested_bindings.read().unwrap().clone();
+    action.binding_routes.write().unwrap().clear();
+
+    for (profile_name, profile_bindings) in &application_bindings.profiles {
+        let action_bindings = match profile_bindings
+            .action_sets
+            .get(&action_set.name)
+            .and_then(|action_set| action_set.actions.get(&action.name))
+        {
+            Some(action_bindings) => action_bindings,
+            None => continue,
+        };
+
+        let profile_path = match instance.string_to_path(profile_name) {
+            Ok(profile_path) => profile_path,
+            Err(_) => continue,
+        };
+
+        let mut bindings = Vec::new();
+        for binding_str in &action_bindings.bindings {
+            let binding = match instance.string_to_path(binding_str) {
+                Ok(binding) => binding,
+                Err(_) => continue,
+            };
+
+            let is_valid = if action.action_type.is_input() {
+                match session
+                    .god_states
+                    .get(&profile_path)
+                    .and_then(|states| states.get(&binding))
+                {
+                    Some(state) => {
+                        let decomposed = action_bindings
+                            .modifiers
+                            .get(binding_str)
+                            .map_or(false, |modifiers| modifiers.decomposition.is_some());
+                        action.action_type.accepts(state.action.action_type)
+                            || decomposed
+                                && state.action.action_type == ActionType::Vector2fInput
+                                && action.action_type.is_primitive()
+                    }
+                    None => false,
+                }
+            } else {
+                session
+                    .god_outputs
+                    .get(&profile_path)
+                    .map_or(false, |outputs| outputs.contains_key(&binding))
+            };
+
+            if is_valid {
+                bindings.push(binding);
+            } else {
+                println!("Ignoring invalid binding {} for {}", binding_str, action.name);
+                continue;
+            }
+
+            let route = action_bindings
+                .modifiers
+                .get(binding_str)
+                .and_then(|modifiers| modifiers.subaction_path.as_ref());
+            if let Some(route) = route {
+                match instance.string_to_path(route) {
+                    Ok(subaction_path) if action.subaction_paths.contains(&subaction_path) => {
+                        action.binding_routes.write().unwrap().insert((profile_path, binding), subaction_path);
+                    }
+                    _ => println!("Ignoring route of {} to {} which is not a subaction path of {}", binding_str, route, action.name),
+                }
+            }
+        }
+
+        action.bindings.write().unwrap().insert(profile_path, bindings);
+    }
+}
+
+///Collects the user's bindings for an action from every profile
+fn get_user_bindings(
+    instance: &InstanceWrapper,
+    application_bindings: &ApplicationBindings,
+    action_set: &ActionSetWrapper,
+    action: &ActionWrapper,
+) -> UserBindings {
+    let mut user_bindings = HashMap::new();
+
+    //Modifications suggested by the application apply until the user edits the profile
+    for (profile_path, modifiers) in action.suggested_modifiers.read().unwrap().iter() {
+        if modifiers.is_empty() {
+            continue;
+        }
+        user_bindings.insert(*profile_path, ActionBindings {
+            bindings: modifiers.keys().cloned().collect(),
+            modifiers: modifiers.clone(),
+            ..Default::default()
+        });
+    }
+
+    for (profile_name, profile_bindings) in &application_bindings.profiles {
+        let action_bindings = match profile_bindings
+            .action_sets
+            .get(&action_set.name)
+            .and_then(|action_set| action_set.actions.get(&action.name))
+        {
+            Some(action_bindings) => action_bindings,
+            None => continue,
+        };
+
+        let mut action_bindings = action_bindings.clone();
+        if let Some(conflict_policy) = action_bindings.conflict_policy {
+            if action.action_type == ActionType::BooleanInput && !conflict_policy.applies_to_booleans() {
+                println!("Ignoring the {:?} conflict policy of {} for {}, boolean actions can only use default or last_changed", conflict_policy, action.name, profile_name);
+                action_bindings.conflict_policy = None;
+            }
+        }
+
+        if let Ok(profile_path) = instance.string_to_path(profile_name) {
+            user_bindings.insert(profile_path, action_bindings);
+        }
+    }
+
+    user_bindings
+}
+
+fn get_haptic_rules(user_bindings: UserBindings) -> HashMap<xr::Path, HapticRules> {
+    user_bindings
+        .into_iter()
+        .filter_map(|(profile, action_bindings)| {
+            action_bindings.haptics.map(|rules| (profile, rules))
+        })
+        .collect()
+}
+
 fn set_info_from_wrapper(wrapper: &ActionSetWrapper) -> ActionSetInfo {
     let mut action_set_info = ActionSetInfo {
         localized_name: wrapper.localized_name.clone(),
@@ -621,6 +1484,81 @@ fn set_info_from_wrapper(wrapper: &ActionSetWrapper) -> ActionSetInfo {
     action_set_info
 }
 
+pub unsafe extern "system" fn get_input_source_localized_name(
+    session: xr::Session,
+    get_info: *const xr::InputSourceLocalizedNameGetInfo,
+    buffer_capacity_input: u32,
+    buffer_count_output: *mut u32,
+    buffer: *mut c_char,
+) -> xr::Result {
+    let session = match session.get_wrapper() {
+        Some(session) => session,
+        None => return xr::Result::ERROR_HANDLE_INVALID,
+    };
+    let instance = session.instance();
+    let get_info = &*get_info;
+
+    if get_info.ty != xr::InputSourceLocalizedNameGetInfo::TYPE || get_info.which_components.is_empty() {
+        return xr::Result::ERROR_VALIDATION_FAILURE;
+    }
+
+    let source = match instance.path_to_string(get_info.source_path) {
+        Ok(source) => source,
+        Err(_) => return xr::Result::ERROR_PATH_INVALID,
+    };
+
+    //Prefer the profile the user is holding, the remapped source may belong to any of them
+    let profiles = input_names::profiles();
+    let active_profile = session
+        .active_profiles
+        .iter()
+        .filter_map(|(user_path, profile)| {
+            let user_path = instance.path_to_string(user_path.0).ok()?;
+            if source.starts_with(&user_path) {
+                instance.path_to_string(profile.read().unwrap().0).ok()
+            } else {
+                None
+            }
+        })
+        .next();
+    let found = active_profile
+        .iter()
+        .filter_map(|profile_name| profiles.get(profile_name))
+        .chain(profiles.values())
+        .find_map(|profile| Some((profile, input_names::find_subpath(profile, &source)?)));
+
+    let (profile, (user_path, _, subpath)) = match found {
+        Some(found) => found,
+        None => {
+            return (instance.core.get_input_source_localized_name)(
+                session.handle,
+                get_info,
+                buffer_capacity_input,
+                buffer_count_output,
+                buffer,
+            )
+        }
+    };
+
+    let mut parts = Vec::new();
+    if get_info.which_components.contains(xr::InputSourceLocalizedNameFlags::USER_PATH) {
+        parts.push(input_names::user_path_name(user_path));
+    }
+    if get_info.which_components.contains(xr::InputSourceLocalizedNameFlags::INTERACTION_PROFILE) {
+        parts.push(profile.title.as_str());
+    }
+    if get_info.which_components.contains(xr::InputSourceLocalizedNameFlags::COMPONENT) {
+        parts.push(subpath.localized_name.as_str());
+    }
+
+    util::write_string(
+        &parts.join(input_names::SEPARATOR),
+        buffer_capacity_input,
+        buffer_count_output,
+        buffer,
+    )
+}
+
 pub unsafe extern "system" fn enumerate_bound_sources_for_action(
     session: xr::Session,
     enumerate_info: *const xr::BoundSourcesForActionEnumerateInfo,
@@ -697,3 +1635,82 @@ pub unsafe extern "system" fn enumerate_bound_sources_for_action(
 
     xr::Result::SUCCESS
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vibration(duration: f64, amplitude: f32) -> xr::HapticVibration {
+        xr::HapticVibration {
+            ty: xr::StructureType::HAPTIC_VIBRATION,
+            next: ptr::null(),
+            duration: xr::Duration::from_nanos((duration * 1e9) as i64),
+            frequency: 0f32,
+            amplitude,
+        }
+    }
+
+    fn shaped(rules: &HapticRules, mut vibration: xr::HapticVibration) -> xr::HapticVibration {
+        shape_vibration(rules, &mut vibration);
+        vibration
+    }
+
+    #[test]
+    fn shape_vibration_defaults() {
+        let vibration = shaped(&HapticRules::default(), vibration(0.1, 0.5));
+        assert_eq!(vibration.duration.as_nanos(), 100_000_000);
+        assert_eq!(vibration.amplitude, 0.5);
+        assert_eq!(vibration.frequency, 0f32);
+    }
+
+    #[test]
+    fn shape_vibration_amplitude() {
+        let rules = HapticRules {
+            amplitude_scale: Some(3f32),
+            ..Default::default()
+        };
+        assert_eq!(shaped(&rules, vibration(0.1, 0.25)).amplitude, 0.75);
+        assert_eq!(shaped(&rules, vibration(0.1, 0.5)).amplitude, 1f32);
+
+        let rules = HapticRules {
+            amplitude_scale: Some(-1f32),
+            ..Default::default()
+        };
+        assert_eq!(shaped(&rules, vibration(0.1, 0.5)).amplitude, 0f32);
+    }
+
+    #[test]
+    fn shape_vibration_duration() {
+        let rules = HapticRules {
+            duration_scale: Some(2f32),
+            min_duration: Some(0.05),
+            max_duration: Some(0.25),
+            ..Default::default()
+        };
+        assert_eq!(shaped(&rules, vibration(0.1, 0.5)).duration.as_nanos(), 200_000_000);
+        assert_eq!(shaped(&rules, vibration(0.2, 0.5)).duration.as_nanos(), 250_000_000);
+        assert_eq!(shaped(&rules, vibration(0.01, 0.5)).duration.as_nanos(), 50_000_000);
+
+        //XR_MIN_HAPTIC_DURATION is only replaced when the user sets a minimum
+        let min_haptic = xr::HapticVibration {
+            duration: xr::Duration::MIN_HAPTIC,
+            ..vibration(0f64, 0.5)
+        };
+        assert_eq!(shaped(&rules, min_haptic).duration.as_nanos(), 50_000_000);
+        let rules = HapticRules {
+            duration_scale: Some(2f32),
+            max_duration: Some(0.25),
+            ..Default::default()
+        };
+        assert_eq!(shaped(&rules, min_haptic).duration.as_nanos(), xr::Duration::MIN_HAPTIC.as_nanos());
+    }
+
+    #[test]
+    fn shape_vibration_frequency() {
+        let rules = HapticRules {
+            frequency: Some(320f32),
+            ..Default::default()
+        };
+        assert_eq!(shaped(&rules, vibration(0.1, 0.5)).frequency, 320f32);
+    }
+}
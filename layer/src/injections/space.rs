@@ -1,5 +1,7 @@
 use std::sync::Weak;
 
+use crate::math;
+use crate::pose_filter;
 use crate::wrappers::*;
 
 use openxr::sys as xr;
@@ -31,5 +33,45 @@ pub unsafe extern "system" fn locate_space(
     };
 
     let result = (space.session().instance().core.locate_space)(space_handle, base_space_handle, time, location);
+    if result.into_raw() < 0 {
+        return result;
+    }
+
+    //Offsets the layer applies to reference spaces itself
+    let (correction, base_correction) = (space.correction(), base_space.correction());
+    if correction.is_some() || base_correction.is_some() {
+        let base_correction = math::pose_inverse(&base_correction.unwrap_or(math::IDENTITY_POSE));
+        location.pose = math::pose_mul(
+            &base_correction,
+            &math::pose_mul(&location.pose, &correction.unwrap_or(math::IDENTITY_POSE)),
+        );
+
+        //Velocities are only turned, a moved reference space doesn't move relative to the runtime's copy
+        if !location.next.is_null() && (*(location.next as *const xr::BaseOutStructure)).ty == xr::SpaceVelocity::TYPE {
+            let velocity = &mut *(location.next as *mut xr::SpaceVelocity);
+            velocity.linear_velocity = math::rotate(base_correction.orientation, velocity.linear_velocity);
+            velocity.angular_velocity = math::rotate(base_correction.orientation, velocity.angular_velocity);
+        }
+    }
+
+    if let SpaceType::ACTION(action_space) = &space.ty {
+        let tracked = xr::SpaceLocationFlags::POSITION_VALID | xr::SpaceLocationFlags::ORIENTATION_VALID;
+        //Read every locate so changes to the settings apply straight away
+        let pose_filter = action_space.cur_binding.read().unwrap().as_ref().and_then(|binding| {
+            space.session().instance().settings.read().unwrap().pose_filters.get(&binding.user_path).copied()
+        });
+        if let Some(pose_filter) = pose_filter {
+            if location.location_flags.contains(tracked) {
+                location.pose = pose_filter::apply(
+                    &pose_filter,
+                    &mut action_space.filter_states.write().unwrap(),
+                    base_space.unchecked_handle,
+                    time,
+                    location.pose,
+                );
+            }
+        }
+    }
+
     result
 }
@@ -0,0 +1,74 @@
+use std::collections::HashMap;
+
+use common::interaction_profiles::{self, InteractionProfile, Subpath};
+use once_cell::sync::Lazy;
+
+static PROFILES: Lazy<HashMap<String, InteractionProfile>> =
+    Lazy::new(|| interaction_profiles::generate().profiles);
+
+///Every known interaction profile by name, generated the first time it is needed
+pub fn profiles() -> &'static HashMap<String, InteractionProfile> {
+    &PROFILES
+}
+
+///Goes between the parts of a localized name e.g. "Valve Index Controller — B"
+pub const SEPARATOR: &'static str = " — ";
+
+///The user facing name of a top level user path
+pub fn user_path_name(user_path: &str) -> &'static str {
+    match user_path {
+        "/user/hand/left" => "Left Hand",
+        "/user/hand/right" => "Right Hand",
+        "/user/head" => "Head",
+        "/user/gamepad" => "Gamepad",
+        "/user/treadmill" => "Treadmill",
+        "/user/eyes_ext" => "Eyes",
+        "/user/vive_tracker_htcx/role/handheld_object" => "Handheld Tracker",
+        "/user/vive_tracker_htcx/role/left_foot" => "Left Foot Tracker",
+        "/user/vive_tracker_htcx/role/right_foot" => "Right Foot Tracker",
+        "/user/vive_tracker_htcx/role/left_shoulder" => "Left Shoulder Tracker",
+        "/user/vive_tracker_htcx/role/right_shoulder" => "Right Shoulder Tracker",
+        "/user/vive_tracker_htcx/role/left_elbow" => "Left Elbow Tracker",
+        "/user/vive_tracker_htcx/role/right_elbow" => "Right Elbow Tracker",
+        "/user/vive_tracker_htcx/role/left_knee" => "Left Knee Tracker",
+        "/user/vive_tracker_htcx/role/right_knee" => "Right Knee Tracker",
+        "/user/vive_tracker_htcx/role/waist" => "Waist Tracker",
+        "/user/vive_tracker_htcx/role/chest" => "Chest Tracker",
+        "/user/vive_tracker_htcx/role/camera" => "Camera Tracker",
+        "/user/vive_tracker_htcx/role/keyboard" => "Keyboard Tracker",
+        _ => "Unknown",
+    }
+}
+
+///Splits a full binding path into its user path and the subpath of the profile it belongs to
+pub fn find_subpath<'a>(
+    profile: &'a InteractionProfile,
+    binding: &'a str,
+) -> Option<(&'a str, &'a str, &'a Subpath)> {
+    let user_path = profile
+        .subaction_paths
+        .iter()
+        .find(|user_path| binding.starts_with(user_path.as_str()))?;
+    let input = &binding[user_path.len()..];
+
+    //The binding may name a feature of the subpath e.g. /input/trigger/value
+    profile
+        .subpaths
+        .iter()
+        .filter(|(subpath, _)| {
+            input == subpath.as_str() || input.starts_with(&format!("{}/", subpath))
+        })
+        .max_by_key(|(subpath, _)| subpath.len())
+        .map(|(subpath, info)| (user_path.as_str(), subpath.as_str(), info))
+}
+
+///e.g. "Right Hand — Trigger"
+pub fn binding_label(profile: &InteractionProfile, binding: &str) -> Option<String> {
+    let (user_path, _, subpath) = find_subpath(profile, binding)?;
+    Some(format!(
+        "{}{}{}",
+        user_path_name(user_path),
+        SEPARATOR,
+        subpath.localized_name
+    ))
+}
@@ -0,0 +1,517 @@
+//! The layer's end of the local control channel, see common::ipc for the protocol
+
+use std::io::{self, BufReader};
+use std::net::Shutdown;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc::{self, SyncSender};
+use std::sync::{Arc, Mutex};
+
+use common::ipc::*;
+use common::serial::{get_uuid, try_write_json};
+use once_cell::sync::Lazy;
+use openxr::sys as xr;
+
+use crate::capture;
+use crate::god_actions::{
+    ActionStatePose, CachedActionStates, CachedActionStatesEnum, GodActionStateEnum, InputBinding,
+    OxideActionState, SubactionBindings,
+};
+use crate::injections::session::bindings_path;
+use crate::space_drag;
+use crate::wrappers::*;
+
+#[cfg(windows)]
+use crate::named_pipe::{PipeListener as Listener, PipeStream as Stream};
+#[cfg(unix)]
+use std::os::unix::net::{UnixListener as Listener, UnixStream as Stream};
+
+///How many messages may be waiting for a tool before it counts as stalled
+const QUEUE_LENGTH: usize = 64;
+
+///Connections which asked to be sent events
+static SUBSCRIBERS: Lazy<Mutex<Vec<Arc<Connection>>>> = Lazy::new(Default::default);
+
+///Connections which asked for a snapshot of a session after every sync
+static INSPECTORS: Lazy<Mutex<Vec<(xr::Session, Arc<Connection>)>>> = Lazy::new(Default::default);
+static INSPECTOR_COUNT: AtomicUsize = AtomicUsize::new(0);
+
+///A tool's end of the channel, messages to it are written by a thread of its own so the application never waits on a tool
+struct Connection {
+    queue: SyncSender<(u64, Arc<Response>)>,
+    stream: Stream,
+    dropped: AtomicBool,
+}
+
+impl Connection {
+    ///Queues a message without blocking, a tool which isn't keeping up is disconnected
+    fn send(&self, id: u64, response: Arc<Response>) -> bool {
+        if self.dropped.load(Ordering::Relaxed) {
+            return false;
+        }
+        if self.queue.try_send((id, response)).is_err() {
+            self.close();
+            return false;
+        }
+        true
+    }
+
+    fn close(&self) {
+        self.dropped.store(true, Ordering::Relaxed);
+        let _ = self.stream.shutdown(Shutdown::Both);
+    }
+}
+
+///Starts listening for tools, only called once as the server lives as long as the process
+///Tools skip the endpoints of processes which have exited as nothing answers on their address
+pub fn start() {
+    let (listener, address) = match bind() {
+        Ok(listener) => listener,
+        Err(why) => {
+            println!("couldn't start the ipc server: {}", why);
+            return;
+        }
+    };
+
+    let pid = std::process::id();
+    let endpoint = Endpoint {
+        version: PROTOCOL_VERSION,
+        pid,
+        address: address.clone(),
+    };
+    if !try_write_json(&endpoint, Path::new(&endpoint_path(pid))) {
+        remove_address(&address);
+        return;
+    }
+
+    std::thread::spawn(move || loop {
+        match accept(&listener) {
+            Ok(stream) => {
+                std::thread::spawn(move || {
+                    if let Err(why) = handle_connection(stream) {
+                        println!("ipc connection closed: {}", why);
+                    }
+                });
+            }
+            Err(why) => println!("ipc connection failed: {}", why),
+        }
+    });
+}
+
+#[cfg(unix)]
+fn bind() -> io::Result<(Listener, String)> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let address = format!("{}{}.sock", ipc_dir(), std::process::id());
+    std::fs::create_dir_all(ipc_dir())?;
+    //Left behind by an earlier process with the same pid
+    let _ = std::fs::remove_file(&address);
+    let listener = Listener::bind(&address)?;
+    //Only the user running the application may control it
+    std::fs::set_permissions(&address, std::fs::Permissions::from_mode(0o600))?;
+    Ok((listener, address))
+}
+
+#[cfg(windows)]
+fn bind() -> io::Result<(Listener, String)> {
+    let address = format!(r"\\.\pipe\oxidexr-{}", std::process::id());
+    Ok((Listener::bind(&address)?, address))
+}
+
+#[cfg(unix)]
+fn accept(listener: &Listener) -> io::Result<Stream> {
+    listener.accept().map(|(stream, _)| stream)
+}
+
+#[cfg(windows)]
+fn accept(listener: &Listener) -> io::Result<Stream> {
+    listener.accept()
+}
+
+#[cfg(unix)]
+fn remove_address(address: &str) {
+    let _ = std::fs::remove_file(address);
+}
+
+///Pipes go away with their last handle
+#[cfg(windows)]
+fn remove_address(_: &str) {}
+
+///Sends an event to every subscribed tool, dropping the ones which have gone away
+pub fn broadcast(event: Event) {
+    let response = Arc::new(Response::Event(event));
+    SUBSCRIBERS
+        .lock()
+        .unwrap()
+        .retain(|connection| connection.send(0, response.clone()));
+}
+
+fn handle_connection(stream: Stream) -> io::Result<()> {
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let (queue, messages) = mpsc::sync_channel::<(u64, Arc<Response>)>(QUEUE_LENGTH);
+    let connection = Arc::new(Connection {
+        queue,
+        stream: stream.try_clone()?,
+        dropped: AtomicBool::new(false),
+    });
+
+    //Responses and events share the queue so events can't land in the middle of a response
+    let mut writer = stream;
+    std::thread::spawn(move || {
+        for (id, response) in messages {
+            if write_message(&mut writer, id, &*response).is_err() {
+                break;
+            }
+        }
+    });
+
+    let result = serve(&mut reader, &connection);
+    connection.close();
+    result
+}
+
+fn serve(reader: &mut BufReader<Stream>, connection: &Arc<Connection>) -> io::Result<()> {
+    while let Some(message) = read_message::<Request, _>(reader)? {
+        let response = if message.version != PROTOCOL_VERSION {
+            Response::Error(format!(
+                "unsupported protocol version {}, expected {}",
+                message.version, PROTOCOL_VERSION
+            ))
+        } else {
+            match handle_request(message.body, connection) {
+                Ok(response) => response,
+                Err(why) => Response::Error(why),
+            }
+        };
+        if !connection.send(message.id, Arc::new(response)) {
+            break;
+        }
+    }
+    Ok(())
+}
+
+fn handle_request(request: Request, connection: &Arc<Connection>) -> Result<Response, String> {
+    match request {
+        Request::ListInstances => Ok(Response::Instances(list_instances())),
+        Request::GetActionStates { session } => Ok(Response::ActionStates(action_states(
+            &get_session(session)?,
+        ))),
+        Request::GetGodStates {
+            session,
+            interaction_profile,
+        } => Ok(Response::GodStates(god_states(
+            &get_session(session)?,
+            interaction_profile.as_deref(),
+        ))),
+        Request::SetBindings { instance, bindings } => {
+            let instance = get_instance(instance)?;
+            //Picked up by every session the next time it syncs
+            if try_write_json(&bindings, Path::new(&bindings_path(&instance))) {
+                Ok(Response::Ok)
+            } else {
+                Err(format!(
+                    "couldn't write the bindings of {}",
+                    get_uuid(&instance.application_name)
+                ))
+            }
+        }
+        Request::Capture { session, request } => {
+            capture::arm(&get_session(session)?, request, false)?;
+            Ok(Response::Ok)
+        }
+        Request::CancelCapture { session } => {
+            capture::cancel(&get_session(session)?);
+            Ok(Response::Ok)
+        }
+        Request::GetPlayspace { session } => Ok(Response::Playspace(
+            *get_session(session)?.playspace_offset.read().unwrap(),
+        )),
+        Request::SetPlayspace {
+            session,
+            playspace,
+            save,
+        } => {
+            let session = get_session(session)?;
+            session.set_playspace_offset(playspace);
+            if save && !space_drag::save_playspace(&session) {
+                return Err("couldn't save the playspace".to_owned());
+            }
+            Ok(Response::Ok)
+        }
+        Request::Subscribe => {
+            SUBSCRIBERS.lock().unwrap().push(connection.clone());
+            Ok(Response::Ok)
+        }
+        Request::Inspect { session } => {
+            let session = get_session(session)?;
+            update_inspectors(|inspectors| inspectors.push((session.handle, connection.clone())));
+            Ok(Response::Ok)
+        }
+    }
+}
+
+fn get_instance(handle: u64) -> Result<Arc<InstanceWrapper>, String> {
+    instances()
+        .get(&xr::Instance::from_raw(handle))
+        .map(|instance| instance.clone())
+        .ok_or_else(|| format!("no instance {}", handle))
+}
+
+fn get_session(handle: u64) -> Result<Arc<SessionWrapper>, String> {
+    sessions()
+        .get(&xr::Session::from_raw(handle))
+        .map(|session| session.clone())
+        .ok_or_else(|| format!("no session {}", handle))
+}
+
+fn list_instances() -> Vec<InstanceInfo> {
+    instances()
+        .iter()
+        .map(|instance| InstanceInfo {
+            handle: instance.handle.into_raw(),
+            application_name: instance.application_name.clone(),
+            engine_name: instance.engine_name.clone(),
+            sessions: instance
+                .sessions
+                .read()
+                .unwrap()
+                .iter()
+                .map(|session| SessionInfo {
+                    handle: session.handle.into_raw(),
+                    active_profiles: active_profiles(instance.value(), session),
+                })
+                .collect(),
+        })
+        .collect()
+}
+
+fn action_states(session: &SessionWrapper) -> Vec<ActionStateInfo> {
+    let cached_action_states = match session.cached_action_states.get() {
+        Some(cached_action_states) => cached_action_states,
+        //No action sets have been attached yet
+        None => return Vec::new(),
+    };
+
+    cached_action_states
+        .iter()
+        .filter_map(|(action, cached_states)| {
+            let action = action.get_wrapper()?;
+            Some(action_state_info(&action, &cached_states.read().unwrap()))
+        })
+        .collect()
+}
+
+///Inputs nothing is bound to are only synced while a capture is armed, so they may be stale
+fn god_states(session: &SessionWrapper, interaction_profile: Option<&str>) -> Vec<GodStateInfo> {
+    session
+        .god_states
+        .values()
+        .flat_map(|bindings| bindings.values())
+        .filter(|binding| {
+            interaction_profile.map_or(true, |profile| profile == binding.action.profile_name_str)
+        })
+        .map(|binding| god_state_info(binding))
+        .collect()
+}
+
+///Sends a snapshot of the session to the tools inspecting it, called after every sync
+pub fn publish_snapshot(session: &SessionWrapper, sync_idx: u64) {
+    //Checked without locking since nobody is inspecting almost all of the time
+    if INSPECTOR_COUNT.load(Ordering::Relaxed) == 0 {
+        return;
+    }
+    if !INSPECTORS
+        .lock()
+        .unwrap()
+        .iter()
+        .any(|(handle, _)| *handle == session.handle)
+    {
+        return;
+    }
+
+    //Built outside of the lock, the writer threads serialize it
+    let response = Arc::new(Response::Event(Event::Snapshot(snapshot(
+        session, sync_idx,
+    ))));
+    update_inspectors(|inspectors| {
+        inspectors.retain(|(handle, connection)| {
+            *handle != session.handle || connection.send(0, response.clone())
+        })
+    });
+}
+
+///Forgets the tools inspecting a session, its handle may be reused
+pub fn session_destroyed(session: xr::Session) {
+    update_inspectors(|inspectors| inspectors.retain(|(handle, _)| *handle != session));
+}
+
+fn update_inspectors<F: FnOnce(&mut Vec<(xr::Session, Arc<Connection>)>)>(update: F) {
+    let mut inspectors = INSPECTORS.lock().unwrap();
+    update(&mut inspectors);
+    INSPECTOR_COUNT.store(inspectors.len(), Ordering::Relaxed);
+}
+
+fn snapshot(session: &SessionWrapper, sync_idx: u64) -> SessionSnapshot {
+    let instance = session.instance();
+    let path_to_string = |path: xr::Path| instance.path_to_string(path).unwrap_or_default();
+
+    let mut actions = Vec::new();
+    if let (Some(input_bindings), Some(cached_action_states)) = (
+        session.input_bindings.get(),
+        session.cached_action_states.get(),
+    ) {
+        for (action_handle, subaction_bindings) in input_bindings.values().flatten() {
+            let action = match action_handle.get_wrapper() {
+                Some(action) => action,
+                None => continue,
+            };
+            let cached_states = match cached_action_states.get(action_handle) {
+                Some(cached_states) => cached_states.read().unwrap(),
+                None => continue,
+            };
+
+            let subaction_states = match &*cached_states {
+                CachedActionStatesEnum::Boolean(states) => subaction_state_values(states),
+                CachedActionStatesEnum::Float(states) => subaction_state_values(states),
+                CachedActionStatesEnum::Vector2f(states) => subaction_state_values(states),
+                CachedActionStatesEnum::Pose(states) => subaction_state_values(states),
+            }
+            .into_iter()
+            .map(|(subaction_path, state)| (path_to_string(subaction_path), state))
+            .collect();
+
+            let bindings = match &*subaction_bindings.read().unwrap() {
+                SubactionBindings::Singleton(bindings) => bindings
+                    .iter()
+                    .map(|binding| (None, god_state_info(binding)))
+                    .collect(),
+                SubactionBindings::Subactions(bindings, _) => bindings
+                    .iter()
+                    .flat_map(|(subaction_path, bindings)| {
+                        bindings
+                            .iter()
+                            .map(move |binding| (*subaction_path, binding))
+                    })
+                    .map(|(subaction_path, binding)| {
+                        (
+                            Some(path_to_string(subaction_path)),
+                            god_state_info(binding),
+                        )
+                    })
+                    .collect(),
+            };
+
+            actions.push(ActionSnapshot {
+                state: action_state_info(&action, &cached_states),
+                subaction_states,
+                bindings,
+            });
+        }
+    }
+
+    SessionSnapshot {
+        session: session.handle.into_raw(),
+        sync_idx,
+        active_profiles: active_profiles(&instance, session),
+        actions,
+    }
+}
+
+fn active_profiles(instance: &InstanceWrapper, session: &SessionWrapper) -> Vec<(String, String)> {
+    session
+        .active_profiles
+        .iter()
+        .filter_map(|(user_path, profile)| {
+            let profile = profile.read().unwrap().0;
+            if profile == xr::Path::NULL {
+                return None;
+            }
+            Some((
+                instance.path_to_string(user_path.0).ok()?,
+                instance.path_to_string(profile).ok()?,
+            ))
+        })
+        .collect()
+}
+
+fn action_state_info(
+    action: &ActionWrapper,
+    cached_states: &CachedActionStatesEnum,
+) -> ActionStateInfo {
+    let state = match cached_states {
+        CachedActionStatesEnum::Boolean(states) => states.main_state.state_value(),
+        CachedActionStatesEnum::Float(states) => states.main_state.state_value(),
+        CachedActionStatesEnum::Vector2f(states) => states.main_state.state_value(),
+        CachedActionStatesEnum::Pose(states) => states.main_state.state_value(),
+    };
+    ActionStateInfo {
+        action_set: action.action_set().name.clone(),
+        action: action.name.clone(),
+        action_type: action.action_type,
+        state,
+    }
+}
+
+fn god_state_info(binding: &InputBinding) -> GodStateInfo {
+    let state = match &*binding.action_state.read().unwrap() {
+        GodActionStateEnum::Boolean(state) => state.state_value(),
+        GodActionStateEnum::Float(state) => state.state_value(),
+        GodActionStateEnum::Vector2f(state) => state.state_value(),
+        GodActionStateEnum::Pose(state) => state.state_value(),
+    };
+    GodStateInfo {
+        interaction_profile: binding.action.profile_name_str.clone(),
+        binding: binding.binding_str.clone(),
+        state,
+    }
+}
+
+fn subaction_state_values<T: OxideActionState + ToStateValue>(
+    states: &CachedActionStates<T>,
+) -> Vec<(xr::Path, StateValue)> {
+    states
+        .subaction_states
+        .iter()
+        .flatten()
+        .map(|(subaction_path, state)| (*subaction_path, state.state_value()))
+        .collect()
+}
+
+trait ToStateValue {
+    fn state_value(&self) -> StateValue;
+}
+
+impl ToStateValue for openxr::ActionState<bool> {
+    fn state_value(&self) -> StateValue {
+        StateValue::Boolean {
+            is_active: self.is_active,
+            value: self.current_state,
+        }
+    }
+}
+
+impl ToStateValue for openxr::ActionState<f32> {
+    fn state_value(&self) -> StateValue {
+        StateValue::Float {
+            is_active: self.is_active,
+            value: self.current_state,
+        }
+    }
+}
+
+impl ToStateValue for openxr::ActionState<openxr::Vector2f> {
+    fn state_value(&self) -> StateValue {
+        StateValue::Vector2f {
+            is_active: self.is_active,
+            value: [self.current_state.x, self.current_state.y],
+        }
+    }
+}
+
+impl ToStateValue for ActionStatePose {
+    fn state_value(&self) -> StateValue {
+        StateValue::Pose {
+            is_active: self.is_active,
+        }
+    }
+}
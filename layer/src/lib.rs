@@ -5,6 +5,23 @@ mod util;
 mod god_actions;
 mod validation;
 mod path;
+mod math;
+mod pose_filter;
+mod local_floor;
+mod eye_gaze;
+mod palm_pose;
+mod space_drag;
+mod extensions;
+mod input_names;
+mod oxidexr_ext;
+mod controller_model;
+mod binding_modification;
+mod vive_tracker;
+mod gamepad;
+mod capture;
+mod ipc;
+#[cfg(windows)]
+mod named_pipe;
 
 use wrappers::*;
 use loader_interfaces::*;
@@ -15,11 +32,17 @@ use openxr::sys::pfn as pfn;
 
 use std::os::raw::c_char;
 use std::ffi::CStr;
+use std::ffi::CString;
+use std::collections::HashSet;
 use std::sync::Arc;
 use std::sync::RwLock;
+use std::sync::Once;
+
+///The control channel is shared by every instance in the process
+static IPC_STARTED: Once = Once::new();
+
 //xrNegotiateLoaderApiLayerInterfaceVersion
 //xrEnumerateApiLayerProperties
-//xrEnumerateInstanceExtensionProperties
 #[no_mangle]
 pub unsafe extern "system" fn xrNegotiateLoaderApiLayerInterface(
     _: *const XrNegotiateLoaderInfo, 
@@ -52,12 +75,60 @@ unsafe extern "system" fn create_api_layer_instance(
     //Get the xrGetInstanceProcAddr func of the layer bellow us
     let get_instance_proc_addr_next: pfn::GetInstanceProcAddr = next_info.next_get_instance_proc_addr; 
 
+    extensions::set_get_instance_proc_addr_next(get_instance_proc_addr_next);
+
+    let runtime_extensions = match extensions::enumerate_extensions_next(get_instance_proc_addr_next) {
+        Ok(properties) => properties.iter().map(|properties| i8_arr_to_owned(&properties.extension_name)).collect::<Vec<_>>(),
+        //The layer's own extensions are still implemented, the application's other extensions are left for the runtime to accept or reject
+        Err(result) => {
+            println!("Failed to enumerate the runtime's extensions: {}", result);
+            Vec::new()
+        }
+    };
+
+    let app_extensions = if (*instance_info).enabled_extension_count == 0 {
+        Vec::new()
+    } else {
+        std::slice::from_raw_parts(
+            (*instance_info).enabled_extension_names,
+            (*instance_info).enabled_extension_count as usize,
+        ).to_vec()
+    };
+    let app_extension_names = app_extensions.iter().map(|ptr| CStr::from_ptr(*ptr).to_string_lossy().into_owned()).collect::<Vec<_>>();
+
+    let layer_extensions = extensions::layer_implemented(&app_extension_names, &runtime_extensions)
+        .iter()
+        .map(|extension| extension.name)
+        .collect::<HashSet<_>>();
+
+    //Extensions the runtime doesn't know about would be rejected by it
+    let mut enabled_extension_names = app_extensions
+        .iter()
+        .copied()
+        .zip(app_extension_names.iter())
+        .filter(|(_, name)| !layer_extensions.contains(name.as_str()) || runtime_extensions.contains(name))
+        .map(|(ptr, _)| ptr)
+        .collect::<Vec<_>>();
+
+    //Trackers can be bound by the user even if the application has never heard of them
+    let vive_tracker_name = CString::new(vive_tracker::EXTENSION_NAME).unwrap();
+    let vive_trackers = runtime_extensions.iter().any(|name| name == vive_tracker::EXTENSION_NAME);
+    let mut hidden_extensions = HashSet::new();
+    if vive_trackers && !app_extension_names.iter().any(|name| name == vive_tracker::EXTENSION_NAME) {
+        enabled_extension_names.push(vive_tracker_name.as_ptr());
+        hidden_extensions.insert(vive_tracker::EXTENSION_NAME);
+    }
+
     //Initialize the layer bellow us
     let result = {
         let mut my_create_info = (*layer_info).clone();
         my_create_info.next_info = next_info.next;
 
-        (next_info.next_create_api_layer_instance)(instance_info, &my_create_info, instance)
+        let mut my_instance_info = *instance_info;
+        my_instance_info.enabled_extension_count = enabled_extension_names.len() as u32;
+        my_instance_info.enabled_extension_names = enabled_extension_names.as_ptr();
+
+        (next_info.next_create_api_layer_instance)(&my_instance_info, &my_create_info, instance)
     };
 
     if result.into_raw() < 0 { return result; }
@@ -74,11 +145,8 @@ unsafe extern "system" fn create_api_layer_instance(
         Err(result) => return result,
     };
 
-    let enabled_ext = std::slice::from_raw_parts(
-        (*instance_info).enabled_extension_names,
-        (*instance_info).enabled_extension_count as usize,
-    )
-    .into_iter()
+    let enabled_ext = enabled_extension_names
+    .iter()
     .map(|ptr| {
         let mut extension_name = [0; xr::MAX_EXTENSION_NAME_SIZE];
         util::place_cstr(&mut extension_name, &CStr::from_ptr(*ptr).to_string_lossy());
@@ -96,6 +164,14 @@ unsafe extern "system" fn create_api_layer_instance(
         Err(result) => return result,
     };
 
+    let enumerate_vive_tracker_paths = if vive_trackers {
+        let mut function = None;
+        get_instance_proc_addr_next(*instance, "xrEnumerateViveTrackerPathsHTCX\0".as_ptr() as *const c_char, &mut function);
+        function.map(|function| std::mem::transmute::<_, vive_tracker::EnumerateViveTrackerPaths>(function))
+    } else {
+        None
+    };
+
     let mut wrapper = wrappers::InstanceWrapper {
         handle: *instance,
         sessions: RwLock::new(Vec::new()),
@@ -112,8 +188,32 @@ unsafe extern "system" fn create_api_layer_instance(
         exts,
 
         get_instance_proc_addr_next,
+
+        settings: Default::default(),
+
+        layer_extensions,
+        hidden_extensions,
+        enumerate_vive_tracker_paths,
+        vive_tracker_roles: Default::default(),
+        pending_events: Default::default(),
+    };
+
+    *wrapper.settings.write().unwrap() = match common::serial::read_json(&wrapper.settings_path()) {
+        Some(settings) => settings,
+        None => Default::default(),
     };
 
+    if wrapper.enumerate_vive_tracker_paths.is_some() {
+        for role in vive_tracker::ROLES {
+            if let Ok(role) = wrapper.string_to_path(role) {
+                wrapper.vive_tracker_roles.write().unwrap().insert(role, false);
+            }
+        }
+        if let Err(result) = vive_tracker::refresh_roles(&wrapper) {
+            println!("failed to enumerate vive trackers: {}", result);
+        }
+    }
+
     match god_actions::create_god_action_sets(&wrapper) {
         Ok(god_action_sets) => {
             wrapper.god_action_sets = god_action_sets;
@@ -129,11 +229,49 @@ unsafe extern "system" fn create_api_layer_instance(
     //Add this instance to the wrapper map
     instances().insert(*instance, Arc::new(wrapper));
 
+    IPC_STARTED.call_once(ipc::start);
+
     result
 }
 
 unsafe extern "system" fn instance_proc_addr(instance: xr::Instance, name: *const c_char, function: *mut Option<pfn::VoidFunction>) -> xr::Result {
+    //The only functions that can be queried without an instance
+    if instance == xr::Instance::NULL {
+        return match CStr::from_ptr(name).to_bytes() {
+            b"xrEnumerateInstanceExtensionProperties" => {
+                *function = Some(std::mem::transmute(extensions::enumerate_instance_extension_properties as pfn::EnumerateInstanceExtensionProperties));
+                xr::Result::SUCCESS
+            },
+            _ => match *extensions::NEXT_GET_INSTANCE_PROC_ADDR.read().unwrap() {
+                Some(get_instance_proc_addr_next) => get_instance_proc_addr_next(instance, name, function),
+                None => xr::Result::ERROR_FUNCTION_UNSUPPORTED,
+            },
+        };
+    }
+
     let instance = InstanceWrapper::from_handle_panic(instance);
+
+    //Functions the runtime has never heard of
+    let own_function: Option<pfn::VoidFunction> = match CStr::from_ptr(name).to_bytes() {
+        b"xrOpenBindingEditorBULLCH" if instance.layer_extension_enabled(oxidexr_ext::EXTENSION_NAME) =>
+            Some(std::mem::transmute(oxidexr_ext::open_binding_editor as oxidexr_ext::OpenBindingEditor)),
+        b"xrGetInputLabelBULLCH" if instance.layer_extension_enabled(oxidexr_ext::EXTENSION_NAME) =>
+            Some(std::mem::transmute(oxidexr_ext::get_input_label as oxidexr_ext::GetInputLabel)),
+        b"xrGetControllerModelKeyMSFT" if instance.layer_extension_enabled(controller_model::EXTENSION_NAME) =>
+            Some(std::mem::transmute(controller_model::get_controller_model_key as pfn::GetControllerModelKeyMSFT)),
+        b"xrLoadControllerModelMSFT" if instance.layer_extension_enabled(controller_model::EXTENSION_NAME) =>
+            Some(std::mem::transmute(controller_model::load_controller_model as pfn::LoadControllerModelMSFT)),
+        b"xrGetControllerModelPropertiesMSFT" if instance.layer_extension_enabled(controller_model::EXTENSION_NAME) =>
+            Some(std::mem::transmute(controller_model::get_controller_model_properties as pfn::GetControllerModelPropertiesMSFT)),
+        b"xrGetControllerModelStateMSFT" if instance.layer_extension_enabled(controller_model::EXTENSION_NAME) =>
+            Some(std::mem::transmute(controller_model::get_controller_model_state as pfn::GetControllerModelStateMSFT)),
+        _ => None,
+    };
+    if own_function.is_some() {
+        *function = own_function;
+        return xr::Result::SUCCESS;
+    }
+
     let result = (instance.get_instance_proc_addr_next)(instance.handle, name, function);
 
     if result.into_raw() < 0 { return result; }
@@ -159,6 +297,8 @@ unsafe extern "system" fn instance_proc_addr(instance: xr::Instance, name: *cons
             
             //Instance methods
             "xrSuggestInteractionProfileBindings" => std::mem::transmute(injections::instance::suggest_interaction_profile_bindings as pfn::SuggestInteractionProfileBindings),
+            "xrPollEvent" => std::mem::transmute(injections::instance::poll_event as pfn::PollEvent),
+            "xrGetSystemProperties" => std::mem::transmute(injections::instance::get_system_properties as pfn::GetSystemProperties),
         
             //Session methods
             "xrAttachSessionActionSets" => std::mem::transmute(injections::session::attach_session_action_sets as pfn::AttachSessionActionSets),
@@ -167,9 +307,14 @@ unsafe extern "system" fn instance_proc_addr(instance: xr::Instance, name: *cons
             "xrGetActionStateFloat" => std::mem::transmute(injections::session::get_action_state_float as pfn::GetActionStateFloat),
             "xrGetActionStateVector2f" => std::mem::transmute(injections::session::get_action_state_vector2f as pfn::GetActionStateVector2f),
             "xrGetActionStatePose" => std::mem::transmute(injections::session::get_action_state_pose as pfn::GetActionStatePose),
+            "xrWaitFrame" => std::mem::transmute(injections::session::wait_frame as pfn::WaitFrame),
+            "xrEndFrame" => std::mem::transmute(injections::session::end_frame as pfn::EndFrame),
+            "xrEnumerateReferenceSpaces" => std::mem::transmute(injections::session::enumerate_reference_spaces as pfn::EnumerateReferenceSpaces),
             "xrLocateViews" => std::mem::transmute(injections::session::locate_views as pfn::LocateViews),
             "xrApplyHapticFeedback" => std::mem::transmute(injections::session::apply_haptic_feedback as pfn::ApplyHapticFeedback),
             "xrStopHapticFeedback" => std::mem::transmute(injections::session::stop_haptic_feedback as pfn::StopHapticFeedback),
+            "xrGetCurrentInteractionProfile" => std::mem::transmute(injections::session::get_current_interaction_profile as pfn::GetCurrentInteractionProfile),
+            "xrGetInputSourceLocalizedName" => std::mem::transmute(injections::session::get_input_source_localized_name as pfn::GetInputSourceLocalizedName),
             "xrEnumerateBoundSourcesForAction" => std::mem::transmute(injections::session::enumerate_bound_sources_for_action as pfn::EnumerateBoundSourcesForAction),
 
             //Space methods
@@ -0,0 +1,117 @@
+use std::ptr;
+
+use openxr::{sys as xr, Result};
+
+use crate::math;
+use crate::util;
+use crate::wrappers::*;
+
+pub const EXTENSION_NAME: &'static str = "XR_EXT_local_floor";
+
+///XR_REFERENCE_SPACE_TYPE_LOCAL_FLOOR_EXT, newer than the headers we build against
+pub const REFERENCE_SPACE_TYPE_LOCAL_FLOOR: xr::ReferenceSpaceType =
+    xr::ReferenceSpaceType::from_raw(1000426000);
+
+///Measures the floor again if needed, LOCAL_FLOOR is LOCAL moved down to the floor of STAGE unless the user has set a height
+pub fn update(session: &SessionWrapper, time: xr::Time) -> Result<()> {
+    if !*session.local_floor_dirty.read().unwrap() {
+        return Ok(());
+    }
+
+    let configured_height = session
+        .instance()
+        .settings
+        .read()
+        .unwrap()
+        .local_floor_height;
+    let height = match configured_height {
+        Some(height) => height,
+        None => match measure_local_height(session, time)? {
+            Some(height) => height,
+            //Try again next frame
+            None => return Ok(()),
+        },
+    };
+
+    *session.local_floor_dirty.write().unwrap() = false;
+
+    if height != *session.local_floor_height.read().unwrap() {
+        println!("Local floor is {}m below LOCAL", height);
+        session.set_local_floor_height(height);
+    }
+
+    Ok(())
+}
+
+///The height of the LOCAL origin above the STAGE floor
+fn measure_local_height(session: &SessionWrapper, time: xr::Time) -> Result<Option<f32>> {
+    if time.as_nanos() == 0 {
+        return Ok(None);
+    }
+
+    let instance = session.instance();
+
+    let create_space = |reference_space_type| {
+        session.create_reference_space(&xr::ReferenceSpaceCreateInfo {
+            ty: xr::ReferenceSpaceCreateInfo::TYPE,
+            next: ptr::null(),
+            reference_space_type,
+            pose_in_reference_space: math::IDENTITY_POSE,
+        })
+    };
+
+    let local = create_space(xr::ReferenceSpaceType::LOCAL)?;
+    let stage = match create_space(xr::ReferenceSpaceType::STAGE) {
+        Ok(stage) => stage,
+        Err(_) => {
+            instance.destroy_space(local)?;
+            return Ok(None);
+        }
+    };
+
+    let mut location = xr::SpaceLocation {
+        ty: xr::SpaceLocation::TYPE,
+        next: ptr::null_mut(),
+        location_flags: xr::SpaceLocationFlags::EMPTY,
+        pose: math::IDENTITY_POSE,
+    };
+    let result =
+        util::check(unsafe { (instance.core.locate_space)(local, stage, time, &mut location) });
+
+    instance.destroy_space(local)?;
+    instance.destroy_space(stage)?;
+    result?;
+
+    Ok(
+        if location
+            .location_flags
+            .contains(xr::SpaceLocationFlags::POSITION_VALID)
+        {
+            Some(location.pose.position.y)
+        } else {
+            None
+        },
+    )
+}
+
+///Passes recentering on to LOCAL_FLOOR spaces, returns an event which should also be sent to the application
+pub fn on_reference_space_change(
+    event: &xr::EventDataReferenceSpaceChangePending,
+) -> Option<xr::EventDataReferenceSpaceChangePending> {
+    let session = event.session.get_wrapper()?;
+    if !session.instance().layer_extension_enabled(EXTENSION_NAME) {
+        return None;
+    }
+
+    if event.reference_space_type == xr::ReferenceSpaceType::STAGE {
+        *session.local_floor_dirty.write().unwrap() = true;
+        None
+    } else if event.reference_space_type == xr::ReferenceSpaceType::LOCAL {
+        *session.local_floor_dirty.write().unwrap() = true;
+        let mut event = *event;
+        event.reference_space_type = REFERENCE_SPACE_TYPE_LOCAL_FLOOR;
+        Some(event)
+    } else {
+        None
+    }
+}
@@ -0,0 +1,201 @@
+use openxr::sys as xr;
+
+pub const IDENTITY_POSE: xr::Posef = xr::Posef {
+    orientation: xr::Quaternionf {
+        x: 0.,
+        y: 0.,
+        z: 0.,
+        w: 1.,
+    },
+    position: xr::Vector3f {
+        x: 0.,
+        y: 0.,
+        z: 0.,
+    },
+};
+
+pub fn quat_mul(a: xr::Quaternionf, b: xr::Quaternionf) -> xr::Quaternionf {
+    xr::Quaternionf {
+        x: a.w * b.x + a.x * b.w + a.y * b.z - a.z * b.y,
+        y: a.w * b.y - a.x * b.z + a.y * b.w + a.z * b.x,
+        z: a.w * b.z + a.x * b.y - a.y * b.x + a.z * b.w,
+        w: a.w * b.w - a.x * b.x - a.y * b.y - a.z * b.z,
+    }
+}
+
+pub fn quat_conjugate(q: xr::Quaternionf) -> xr::Quaternionf {
+    xr::Quaternionf {
+        x: -q.x,
+        y: -q.y,
+        z: -q.z,
+        w: q.w,
+    }
+}
+
+pub fn quat_from_axis_angle(axis: xr::Vector3f, radians: f32) -> xr::Quaternionf {
+    let (sin, cos) = (radians / 2.).sin_cos();
+    xr::Quaternionf {
+        x: axis.x * sin,
+        y: axis.y * sin,
+        z: axis.z * sin,
+        w: cos,
+    }
+}
+
+///Rotation of `degrees` around the x, y and z axes, applied in that order
+pub fn quat_from_euler_degrees(degrees: [f32; 3]) -> xr::Quaternionf {
+    let x = quat_from_axis_angle(
+        xr::Vector3f {
+            x: 1.,
+            y: 0.,
+            z: 0.,
+        },
+        degrees[0].to_radians(),
+    );
+    let y = quat_from_axis_angle(
+        xr::Vector3f {
+            x: 0.,
+            y: 1.,
+            z: 0.,
+        },
+        degrees[1].to_radians(),
+    );
+    let z = quat_from_axis_angle(
+        xr::Vector3f {
+            x: 0.,
+            y: 0.,
+            z: 1.,
+        },
+        degrees[2].to_radians(),
+    );
+    quat_mul(z, quat_mul(y, x))
+}
+
+pub fn rotate(q: xr::Quaternionf, v: xr::Vector3f) -> xr::Vector3f {
+    let p = quat_mul(
+        quat_mul(
+            q,
+            xr::Quaternionf {
+                x: v.x,
+                y: v.y,
+                z: v.z,
+                w: 0.,
+            },
+        ),
+        quat_conjugate(q),
+    );
+    xr::Vector3f {
+        x: p.x,
+        y: p.y,
+        z: p.z,
+    }
+}
+
+pub fn vec_add(a: xr::Vector3f, b: xr::Vector3f) -> xr::Vector3f {
+    xr::Vector3f {
+        x: a.x + b.x,
+        y: a.y + b.y,
+        z: a.z + b.z,
+    }
+}
+
+///The pose `b` (which is relative to `a`) made relative to whatever `a` is relative to
+pub fn pose_mul(a: &xr::Posef, b: &xr::Posef) -> xr::Posef {
+    xr::Posef {
+        orientation: quat_mul(a.orientation, b.orientation),
+        position: vec_add(a.position, rotate(a.orientation, b.position)),
+    }
+}
+
+pub fn pose_inverse(pose: &xr::Posef) -> xr::Posef {
+    let orientation = quat_conjugate(pose.orientation);
+    let position = rotate(orientation, pose.position);
+    xr::Posef {
+        orientation,
+        position: xr::Vector3f {
+            x: -position.x,
+            y: -position.y,
+            z: -position.z,
+        },
+    }
+}
+
+pub fn vec_sub(a: xr::Vector3f, b: xr::Vector3f) -> xr::Vector3f {
+    xr::Vector3f {
+        x: a.x - b.x,
+        y: a.y - b.y,
+        z: a.z - b.z,
+    }
+}
+
+pub fn vec_length(v: xr::Vector3f) -> f32 {
+    (v.x * v.x + v.y * v.y + v.z * v.z).sqrt()
+}
+
+pub fn vec_lerp(a: xr::Vector3f, b: xr::Vector3f, t: f32) -> xr::Vector3f {
+    xr::Vector3f {
+        x: a.x + (b.x - a.x) * t,
+        y: a.y + (b.y - a.y) * t,
+        z: a.z + (b.z - a.z) * t,
+    }
+}
+
+fn quat_dot(a: xr::Quaternionf, b: xr::Quaternionf) -> f32 {
+    a.x * b.x + a.y * b.y + a.z * b.z + a.w * b.w
+}
+
+///Radians needed to rotate from `a` to `b`
+pub fn quat_angle(a: xr::Quaternionf, b: xr::Quaternionf) -> f32 {
+    2. * quat_dot(a, b).abs().min(1.).acos()
+}
+
+pub fn quat_slerp(a: xr::Quaternionf, b: xr::Quaternionf, t: f32) -> xr::Quaternionf {
+    //Take the short way around
+    let (b, dot) = match quat_dot(a, b) {
+        dot if dot < 0. => (
+            xr::Quaternionf {
+                x: -b.x,
+                y: -b.y,
+                z: -b.z,
+                w: -b.w,
+            },
+            -dot,
+        ),
+        dot => (b, dot),
+    };
+
+    let (wa, wb) = if dot > 0.9995 {
+        (1. - t, t)
+    } else {
+        let theta = dot.acos();
+        let sin = theta.sin();
+        (((1. - t) * theta).sin() / sin, (t * theta).sin() / sin)
+    };
+
+    let q = xr::Quaternionf {
+        x: a.x * wa + b.x * wb,
+        y: a.y * wa + b.y * wb,
+        z: a.z * wa + b.z * wb,
+        w: a.w * wa + b.w * wb,
+    };
+    let length = quat_dot(q, q).sqrt();
+    xr::Quaternionf {
+        x: q.x / length,
+        y: q.y / length,
+        z: q.z / length,
+        w: q.w / length,
+    }
+}
+
+///Radians counter-clockwise around the up axis that the forward (-z) direction has been turned
+pub fn quat_yaw(q: xr::Quaternionf) -> f32 {
+    let forward = rotate(
+        q,
+        xr::Vector3f {
+            x: 0.,
+            y: 0.,
+            z: -1.,
+        },
+    );
+    (-forward.x).atan2(-forward.z)
+}
@@ -0,0 +1,178 @@
+//! Just enough of windows named pipes for the ipc server, std only has unix sockets
+//!
+//! Every operation is overlapped, a synchronous pipe handle would make the writer thread wait for the reader thread.
+
+use std::ffi::OsStr;
+use std::io::{self, Read, Write};
+use std::net::Shutdown;
+use std::os::windows::ffi::OsStrExt;
+use std::ptr;
+use std::sync::{Arc, Mutex};
+
+use winapi::shared::minwindef::{BOOL, FALSE, TRUE};
+use winapi::shared::winerror::{ERROR_BROKEN_PIPE, ERROR_IO_PENDING, ERROR_PIPE_CONNECTED};
+use winapi::um::fileapi::{ReadFile, WriteFile};
+use winapi::um::handleapi::{CloseHandle, INVALID_HANDLE_VALUE};
+use winapi::um::ioapiset::GetOverlappedResult;
+use winapi::um::minwinbase::OVERLAPPED;
+use winapi::um::namedpipeapi::{ConnectNamedPipe, CreateNamedPipeW, DisconnectNamedPipe};
+use winapi::um::synchapi::CreateEventW;
+use winapi::um::winbase::{
+    FILE_FLAG_FIRST_PIPE_INSTANCE, FILE_FLAG_OVERLAPPED, PIPE_ACCESS_DUPLEX, PIPE_READMODE_BYTE,
+    PIPE_REJECT_REMOTE_CLIENTS, PIPE_TYPE_BYTE, PIPE_UNLIMITED_INSTANCES, PIPE_WAIT,
+};
+use winapi::um::winnt::HANDLE;
+
+const BUFFER_SIZE: u32 = 4096;
+
+struct Handle(HANDLE);
+
+unsafe impl Send for Handle {}
+unsafe impl Sync for Handle {}
+
+impl Drop for Handle {
+    fn drop(&mut self) {
+        unsafe {
+            CloseHandle(self.0);
+        }
+    }
+}
+
+pub struct PipeListener {
+    name: Vec<u16>,
+    ///The instance the next client will connect to
+    next: Mutex<Option<PipeStream>>,
+}
+
+impl PipeListener {
+    ///Fails if another process already owns a pipe with this name
+    pub fn bind(name: &str) -> io::Result<PipeListener> {
+        let name = OsStr::new(name)
+            .encode_wide()
+            .chain(Some(0))
+            .collect::<Vec<_>>();
+        let first = create_instance(&name, FILE_FLAG_FIRST_PIPE_INSTANCE)?;
+        Ok(PipeListener {
+            name,
+            next: Mutex::new(Some(first)),
+        })
+    }
+
+    pub fn accept(&self) -> io::Result<PipeStream> {
+        let pipe = match self.next.lock().unwrap().take() {
+            Some(pipe) => pipe,
+            None => create_instance(&self.name, 0)?,
+        };
+        pipe.overlapped(|handle, overlapped| unsafe { ConnectNamedPipe(handle, overlapped) })?;
+        //Clients which arrive before the next accept would find no instance
+        *self.next.lock().unwrap() = create_instance(&self.name, 0).ok();
+        Ok(pipe)
+    }
+}
+
+fn create_instance(name: &[u16], flags: u32) -> io::Result<PipeStream> {
+    //The default security descriptor only lets the same user (and administrators) write to the pipe
+    let handle = unsafe {
+        CreateNamedPipeW(
+            name.as_ptr(),
+            PIPE_ACCESS_DUPLEX | FILE_FLAG_OVERLAPPED | flags,
+            PIPE_TYPE_BYTE | PIPE_READMODE_BYTE | PIPE_WAIT | PIPE_REJECT_REMOTE_CLIENTS,
+            PIPE_UNLIMITED_INSTANCES,
+            BUFFER_SIZE,
+            BUFFER_SIZE,
+            0,
+            ptr::null_mut(),
+        )
+    };
+    if handle == INVALID_HANDLE_VALUE {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(PipeStream(Arc::new(Handle(handle))))
+}
+
+#[derive(Clone)]
+pub struct PipeStream(Arc<Handle>);
+
+impl PipeStream {
+    pub fn try_clone(&self) -> io::Result<PipeStream> {
+        Ok(self.clone())
+    }
+
+    ///Disconnects the client, which also fails any read or write still waiting
+    pub fn shutdown(&self, _: Shutdown) -> io::Result<()> {
+        if unsafe { DisconnectNamedPipe(self.0 .0) } == FALSE {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+
+    ///Starts an operation and waits for it to finish, returning the number of bytes transferred
+    fn overlapped<F>(&self, operation: F) -> io::Result<usize>
+    where
+        F: FnOnce(HANDLE, *mut OVERLAPPED) -> BOOL,
+    {
+        unsafe {
+            let event = CreateEventW(ptr::null_mut(), TRUE, FALSE, ptr::null());
+            if event.is_null() {
+                return Err(io::Error::last_os_error());
+            }
+            let event = Handle(event);
+
+            let mut overlapped = std::mem::zeroed::<OVERLAPPED>();
+            overlapped.hEvent = event.0;
+
+            if operation(self.0 .0, &mut overlapped) == FALSE {
+                let error = io::Error::last_os_error();
+                match error.raw_os_error() {
+                    Some(code) if code == ERROR_IO_PENDING as i32 => (),
+                    //The client connected between creating the instance and waiting for it
+                    Some(code) if code == ERROR_PIPE_CONNECTED as i32 => return Ok(0),
+                    _ => return Err(error),
+                }
+            }
+
+            let mut transferred = 0;
+            if GetOverlappedResult(self.0 .0, &mut overlapped, &mut transferred, TRUE) == FALSE {
+                return Err(io::Error::last_os_error());
+            }
+            Ok(transferred as usize)
+        }
+    }
+}
+
+impl Read for PipeStream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let result = self.overlapped(|handle, overlapped| unsafe {
+            ReadFile(
+                handle,
+                buf.as_mut_ptr() as *mut _,
+                buf.len() as u32,
+                ptr::null_mut(),
+                overlapped,
+            )
+        });
+        match result {
+            //The client has closed its end
+            Err(error) if error.raw_os_error() == Some(ERROR_BROKEN_PIPE as i32) => Ok(0),
+            result => result,
+        }
+    }
+}
+
+impl Write for PipeStream {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.overlapped(|handle, overlapped| unsafe {
+            WriteFile(
+                handle,
+                buf.as_ptr() as *const _,
+                buf.len() as u32,
+                ptr::null_mut(),
+                overlapped,
+            )
+        })
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
@@ -0,0 +1,150 @@
+use std::os::raw::{c_char, c_void};
+use std::process::Command;
+
+use common::interaction_profiles;
+use openxr::sys as xr;
+
+use crate::injections::instance::queue_event;
+use crate::input_names;
+use crate::util;
+use crate::wrappers::*;
+
+///Lets applications talk to the layer
+pub const EXTENSION_NAME: &'static str = "XR_BULLCH_oxidexr";
+
+///The extension is not registered with Khronos, the number is picked from the top of the vendor range to avoid clashes
+const EXTENSION_NUMBER: i32 = 9999;
+
+pub const TYPE_EVENT_DATA_BINDINGS_CHANGED: xr::StructureType =
+    xr::StructureType::from_raw(1_000_000_000 + (EXTENSION_NUMBER - 1) * 1000);
+
+///Set by the user to point at the binding editor, otherwise it is looked up on PATH
+const GUI_ENV: &'static str = "OXIDEXR_GUI";
+const GUI_EXECUTABLE: &'static str = "gui";
+
+///Sent whenever the user's bindings for the session have been reloaded
+#[repr(C)]
+#[derive(Copy, Clone, Debug)]
+pub struct EventDataBindingsChanged {
+    pub ty: xr::StructureType,
+    pub next: *const c_void,
+    pub session: xr::Session,
+}
+
+pub type OpenBindingEditor = unsafe extern "system" fn(session: xr::Session) -> xr::Result;
+
+pub type GetInputLabel = unsafe extern "system" fn(
+    session: xr::Session,
+    action: xr::Action,
+    subaction_path: xr::Path,
+    buffer_capacity_input: u32,
+    buffer_count_output: *mut u32,
+    buffer: *mut c_char,
+) -> xr::Result;
+
+pub fn bindings_changed(session: &SessionWrapper) {
+    let instance = session.instance();
+    if !instance.layer_extension_enabled(EXTENSION_NAME) {
+        return;
+    }
+
+    queue_event(
+        &instance,
+        &EventDataBindingsChanged {
+            ty: TYPE_EVENT_DATA_BINDINGS_CHANGED,
+            next: std::ptr::null(),
+            session: session.handle,
+        },
+    );
+}
+
+///Opens the binding editor for the application's action sets
+pub unsafe extern "system" fn open_binding_editor(session: xr::Session) -> xr::Result {
+    let session = match session.get_wrapper() {
+        Some(session) => session,
+        None => return xr::Result::ERROR_HANDLE_INVALID,
+    };
+    let instance = session.instance();
+
+    let executable = std::env::var(GUI_ENV).unwrap_or_else(|_| GUI_EXECUTABLE.to_owned());
+    match Command::new(&executable)
+        .arg(&instance.application_name)
+        .spawn()
+    {
+        Ok(_) => xr::Result::SUCCESS,
+        Err(err) => {
+            println!("Failed to open binding editor {}: {}", executable, err);
+            xr::Result::ERROR_RUNTIME_FAILURE
+        }
+    }
+}
+
+///The user facing names of the inputs currently bound to an action, so that button prompts follow the user's bindings
+pub unsafe extern "system" fn get_input_label(
+    session: xr::Session,
+    action: xr::Action,
+    subaction_path: xr::Path,
+    buffer_capacity_input: u32,
+    buffer_count_output: *mut u32,
+    buffer: *mut c_char,
+) -> xr::Result {
+    let session = match session.get_wrapper() {
+        Some(session) => session,
+        None => return xr::Result::ERROR_HANDLE_INVALID,
+    };
+    let action = match action.get_wrapper() {
+        Some(action) => action,
+        None => return xr::Result::ERROR_HANDLE_INVALID,
+    };
+    let instance = session.instance();
+
+    if subaction_path != xr::Path::NULL && !action.subaction_paths.contains(&subaction_path) {
+        return xr::Result::ERROR_PATH_UNSUPPORTED;
+    }
+
+    let profiles = interaction_profiles::generate().profiles;
+    let bindings = action.bindings.read().unwrap();
+
+    let mut labels = Vec::new();
+    for (user_path, active_profile) in &session.active_profiles {
+        if subaction_path != xr::Path::NULL && user_path.0 != subaction_path {
+            continue;
+        }
+        let active_profile = active_profile.read().unwrap().0;
+        let profile = match instance
+            .path_to_string(active_profile)
+            .ok()
+            .and_then(|profile_name| profiles.get(&profile_name))
+        {
+            Some(profile) => profile,
+            None => continue,
+        };
+        let user_path = match instance.path_to_string(user_path.0) {
+            Ok(user_path) => user_path,
+            Err(_) => continue,
+        };
+
+        for binding in bindings.get(&active_profile).into_iter().flatten() {
+            let binding = match instance.path_to_string(*binding) {
+                Ok(binding) => binding,
+                Err(_) => continue,
+            };
+            if !binding.starts_with(&user_path) {
+                continue;
+            }
+            if let Some(label) = input_names::binding_label(profile, &binding) {
+                if !labels.contains(&label) {
+                    labels.push(label);
+                }
+            }
+        }
+    }
+    labels.sort();
+
+    util::write_string(
+        &labels.join(", "),
+        buffer_capacity_input,
+        buffer_count_output,
+        buffer,
+    )
+}
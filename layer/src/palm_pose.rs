@@ -0,0 +1,108 @@
+use openxr::sys as xr;
+
+pub const EXTENSION_NAME: &'static str = "XR_EXT_palm_pose";
+
+pub const SUBPATH: &'static str = "/input/palm_ext";
+
+///Where the center of the right palm sits in the grip space of each profile's controller, the left hand is mirrored
+///Handles differ in thickness and some controllers hold the hand further along them, so one offset doesn't fit every profile
+const PALM_OFFSETS: &[(&'static str, [f32; 3])] = &[
+    ("/interaction_profiles/htc/vive_controller", [0.045, 0., 0.]),
+    (
+        "/interaction_profiles/microsoft/motion_controller",
+        [0.04, 0., 0.],
+    ),
+    (
+        "/interaction_profiles/oculus/touch_controller",
+        [0.035, 0., 0.01],
+    ),
+    (
+        "/interaction_profiles/valve/index_controller",
+        [0.03, 0., 0.015],
+    ),
+];
+
+///Used for profiles missing from PALM_OFFSETS, the distance from the center of a typical handle to the palm holding it
+const DEFAULT_PALM_OFFSET: [f32; 3] = [0.035, 0., 0.];
+
+///The palm pose relative to the grip pose of a hand
+///The grip's +X axis is the palm's normal (into the palm for the right hand, out of it for the left) which the palm pose wants as +Y pointing out of the back of the hand,
+///the palm's -Z points along the flattened fingers
+///Anything the table gets wrong for a controller can still be corrected with a pose_offset on the palm_ext binding
+pub fn palm_in_grip(profile: &str, right_hand: bool) -> xr::Posef {
+    let offset = PALM_OFFSETS
+        .iter()
+        .find(|(name, _)| *name == profile)
+        .map_or(DEFAULT_PALM_OFFSET, |(_, offset)| *offset);
+
+    if right_hand {
+        xr::Posef {
+            orientation: xr::Quaternionf {
+                x: -0.5,
+                y: -0.5,
+                z: -0.5,
+                w: 0.5,
+            },
+            position: xr::Vector3f {
+                x: offset[0],
+                y: offset[1],
+                z: offset[2],
+            },
+        }
+    } else {
+        xr::Posef {
+            orientation: xr::Quaternionf {
+                x: -0.5,
+                y: 0.5,
+                z: 0.5,
+                w: 0.5,
+            },
+            position: xr::Vector3f {
+                x: -offset[0],
+                y: offset[1],
+                z: offset[2],
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn position(pose: &xr::Posef) -> [f32; 3] {
+        [pose.position.x, pose.position.y, pose.position.z]
+    }
+
+    #[test]
+    fn offsets_per_profile() {
+        let vive = "/interaction_profiles/htc/vive_controller";
+        let index = "/interaction_profiles/valve/index_controller";
+
+        assert_eq!(position(&palm_in_grip(vive, true)), [0.045, 0., 0.]);
+        assert_eq!(position(&palm_in_grip(vive, false)), [-0.045, 0., 0.]);
+        assert_eq!(position(&palm_in_grip(index, true)), [0.03, 0., 0.015]);
+        assert_eq!(position(&palm_in_grip(index, false)), [-0.03, 0., 0.015]);
+    }
+
+    #[test]
+    fn unknown_profiles_fall_back() {
+        let profile = "/interaction_profiles/khr/simple_controller";
+        assert_eq!(position(&palm_in_grip(profile, true)), DEFAULT_PALM_OFFSET);
+        assert_eq!(
+            position(&palm_in_grip(profile, false)),
+            [-DEFAULT_PALM_OFFSET[0], 0., 0.]
+        );
+    }
+
+    #[test]
+    fn hands_share_orientation_axes() {
+        //Both palms face their own +Y away from the grip's X axis, so the hands are mirror images across it
+        for profile in PALM_OFFSETS.iter().map(|(name, _)| *name) {
+            let right = palm_in_grip(profile, true).orientation;
+            let left = palm_in_grip(profile, false).orientation;
+            assert_eq!((right.x, right.w), (left.x, left.w));
+            assert_eq!((right.y, right.z), (-left.y, -left.z));
+        }
+    }
+}
@@ -0,0 +1,248 @@
+use std::collections::HashMap;
+
+use common::application_settings::PoseFilter;
+use openxr::sys as xr;
+
+use crate::math;
+
+///Locates further apart than this restart the filter rather than gliding in from a stale pose (e.g. after tracking was lost)
+const MAX_GAP: f32 = 0.5;
+
+///What a pose filter remembers between locates in a base space
+pub type PoseFilterStates = HashMap<xr::Space /* base space */, PoseFilterState>;
+
+pub struct PoseFilterState {
+    time: xr::Time,
+    pose: xr::Posef,
+    ///Smoothed speeds used by the 1€ filter (m/s and rad/s)
+    linear_speed: f32,
+    angular_speed: f32,
+}
+
+///Filters a newly located pose, each base space is filtered separately since poses in different spaces can't be blended
+pub fn apply(
+    filter: &PoseFilter,
+    states: &mut PoseFilterStates,
+    base_space: xr::Space,
+    time: xr::Time,
+    pose: xr::Posef,
+) -> xr::Posef {
+    let restart = PoseFilterState {
+        time,
+        pose,
+        linear_speed: 0.,
+        angular_speed: 0.,
+    };
+    let prev = match states.get_mut(&base_space) {
+        Some(prev) => prev,
+        None => {
+            states.insert(base_space, restart);
+            return pose;
+        }
+    };
+
+    //Locating the same time again gives the same result, locating the past is left unfiltered
+    if time.as_nanos() == prev.time.as_nanos() {
+        return prev.pose;
+    } else if time.as_nanos() < prev.time.as_nanos() {
+        return pose;
+    }
+
+    let delta = (time.as_nanos() - prev.time.as_nanos()) as f32 / 1_000_000_000f32;
+    if delta > MAX_GAP {
+        *prev = restart;
+        return pose;
+    }
+
+    let (position_t, orientation_t) = match *filter {
+        PoseFilter::Exponential { time_constant } => {
+            let t = if time_constant > 0. {
+                1. - (-delta / time_constant).exp()
+            } else {
+                1.
+            };
+            (t, t)
+        }
+        PoseFilter::OneEuro {
+            min_cutoff,
+            beta,
+            derivative_cutoff,
+        } => {
+            let speed_t = smoothing_factor(delta, derivative_cutoff);
+
+            let linear_speed =
+                math::vec_length(math::vec_sub(pose.position, prev.pose.position)) / delta;
+            prev.linear_speed += (linear_speed - prev.linear_speed) * speed_t;
+
+            let angular_speed = math::quat_angle(prev.pose.orientation, pose.orientation) / delta;
+            prev.angular_speed += (angular_speed - prev.angular_speed) * speed_t;
+
+            (
+                smoothing_factor(delta, min_cutoff + beta * prev.linear_speed),
+                smoothing_factor(delta, min_cutoff + beta * prev.angular_speed),
+            )
+        }
+    };
+
+    prev.pose = xr::Posef {
+        orientation: math::quat_slerp(prev.pose.orientation, pose.orientation, orientation_t),
+        position: math::vec_lerp(prev.pose.position, pose.position, position_t),
+    };
+    prev.time = time;
+    prev.pose
+}
+
+///How far to move towards a new sample for a low pass filter with the given cutoff frequency
+fn smoothing_factor(delta: f32, cutoff: f32) -> f32 {
+    let r = 2. * std::f32::consts::PI * cutoff * delta;
+    r / (r + 1.)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXPONENTIAL: PoseFilter = PoseFilter::Exponential { time_constant: 0.1 };
+    const ONE_EURO: PoseFilter = PoseFilter::OneEuro {
+        min_cutoff: 1.,
+        beta: 0.5,
+        derivative_cutoff: 1.,
+    };
+
+    fn time(seconds: f32) -> xr::Time {
+        xr::Time::from_nanos((seconds * 1e9) as i64)
+    }
+
+    fn at(x: f32) -> xr::Posef {
+        xr::Posef {
+            position: xr::Vector3f { x, y: 0., z: 0. },
+            ..math::IDENTITY_POSE
+        }
+    }
+
+    fn base(raw: u64) -> xr::Space {
+        xr::Space::from_raw(raw)
+    }
+
+    #[test]
+    fn exponential() {
+        let mut states = PoseFilterStates::new();
+        assert_eq!(
+            apply(&EXPONENTIAL, &mut states, base(1), time(1.), at(0.))
+                .position
+                .x,
+            0.
+        );
+
+        //One time constant covers 1 - 1/e of the distance
+        let x = apply(&EXPONENTIAL, &mut states, base(1), time(1.1), at(1.))
+            .position
+            .x;
+        assert!((x - (1. - (-1f32).exp())).abs() < 1e-4, "{}", x);
+
+        //Locating the same time again repeats the result, the past is left alone
+        assert_eq!(
+            apply(&EXPONENTIAL, &mut states, base(1), time(1.1), at(5.))
+                .position
+                .x,
+            x
+        );
+        assert_eq!(
+            apply(&EXPONENTIAL, &mut states, base(1), time(1.05), at(5.))
+                .position
+                .x,
+            5.
+        );
+
+        let unfiltered = PoseFilter::Exponential { time_constant: 0. };
+        let x = apply(&unfiltered, &mut states, base(1), time(1.2), at(2.))
+            .position
+            .x;
+        assert!((x - 2.).abs() < 1e-6, "{}", x);
+    }
+
+    #[test]
+    fn base_spaces_are_separate() {
+        let mut states = PoseFilterStates::new();
+        apply(&EXPONENTIAL, &mut states, base(1), time(1.), at(0.));
+        apply(&EXPONENTIAL, &mut states, base(2), time(1.), at(10.));
+
+        //Switching base spaces every locate must not blend poses from the other one
+        let x = apply(&EXPONENTIAL, &mut states, base(1), time(1.1), at(1.))
+            .position
+            .x;
+        assert!(x > 0.5 && x < 1., "{}", x);
+        let x = apply(&EXPONENTIAL, &mut states, base(2), time(1.1), at(11.))
+            .position
+            .x;
+        assert!(x > 10.5 && x < 11., "{}", x);
+    }
+
+    #[test]
+    fn restarts_after_gap() {
+        let mut states = PoseFilterStates::new();
+        apply(&EXPONENTIAL, &mut states, base(1), time(1.), at(0.));
+        assert_eq!(
+            apply(&EXPONENTIAL, &mut states, base(1), time(2.), at(1.))
+                .position
+                .x,
+            1.
+        );
+        let x = apply(&EXPONENTIAL, &mut states, base(1), time(2.1), at(2.))
+            .position
+            .x;
+        assert!(x > 1.5 && x < 2., "{}", x);
+    }
+
+    #[test]
+    fn one_euro_follows_fast_movement() {
+        let frame = 1. / 90.;
+
+        //Small jitter while still is mostly removed
+        let mut states = PoseFilterStates::new();
+        let mut x = 0.;
+        for i in 0..90 {
+            let jitter = if i % 2 == 0 { 0.001 } else { -0.001 };
+            x = apply(
+                &ONE_EURO,
+                &mut states,
+                base(1),
+                time(i as f32 * frame),
+                at(jitter),
+            )
+            .position
+            .x;
+        }
+        assert!(x.abs() < 0.0005, "{}", x);
+
+        //A fast sweep raises the cutoff so the filter lags far less than it would at the minimum cutoff
+        let mut states = PoseFilterStates::new();
+        let speed = 2.;
+        for i in 0..90 {
+            x = apply(
+                &ONE_EURO,
+                &mut states,
+                base(1),
+                time(i as f32 * frame),
+                at(i as f32 * frame * speed),
+            )
+            .position
+            .x;
+        }
+        let target = 89. * frame * speed;
+        let min_cutoff_lag = speed / (2. * std::f32::consts::PI);
+        assert!(
+            target - x < min_cutoff_lag / 2.,
+            "lagged {} behind",
+            target - x
+        );
+    }
+
+    #[test]
+    fn smoothing() {
+        assert_eq!(smoothing_factor(0., 1.), 0.);
+        let t = smoothing_factor(0.01, 1.);
+        assert!(t > 0. && t < smoothing_factor(0.01, 10.));
+        assert!(smoothing_factor(1., 1000.) > 0.99);
+    }
+}
@@ -0,0 +1,225 @@
+use std::ops::Deref;
+use std::path::Path;
+use std::ptr;
+use std::sync::Arc;
+
+use common::application_settings::{PlayspaceOffset, SpaceDrag, SpaceDragMode};
+use common::serial::try_write_json;
+use openxr::{sys as xr, Result};
+
+use crate::god_actions::{Binding, GodActionStateEnum, InputBinding};
+use crate::math;
+use crate::util;
+use crate::wrappers::*;
+
+pub struct SpaceDragState {
+    config: SpaceDrag,
+    ///Holding onto the chord's bindings also keeps their god states synced
+    chord: Vec<Arc<InputBinding>>,
+    grip_space: xr::Space,
+    ///The runtime's LOCAL space without the playspace offset
+    base_space: xr::Space,
+    ///The grip pose and playspace offset from when the chord was pressed
+    start: Option<(xr::Posef, PlayspaceOffset)>,
+}
+
+///Replaces the session's space drag with one using the given config
+pub fn configure(session: &SessionWrapper, config: Option<&SpaceDrag>) -> Result<()> {
+    let instance = session.instance();
+
+    let mut space_drag = session.space_drag.write().unwrap();
+    if let Some(old) = space_drag.take() {
+        instance.destroy_space(old.grip_space)?;
+        instance.destroy_space(old.base_space)?;
+    }
+
+    let config = match config {
+        Some(config) => config,
+        None => return Ok(()),
+    };
+
+    let states = match session
+        .god_states
+        .get(&instance.string_to_path(&config.interaction_profile)?)
+    {
+        Some(states) => states,
+        None => {
+            println!("Unknown space drag profile {}", config.interaction_profile);
+            return Ok(());
+        }
+    };
+
+    if config.chord.is_empty() {
+        println!("Ignoring space drag with an empty chord");
+        return Ok(());
+    }
+
+    let mut chord = Vec::new();
+    for binding_str in &config.chord {
+        match states.get(&instance.string_to_path(binding_str)?) {
+            Some(binding) => chord.push(binding.clone()),
+            None => {
+                println!("Ignoring space drag, {} is not an input", binding_str);
+                return Ok(());
+            }
+        }
+    }
+
+    let grip =
+        match states.get(&instance.string_to_path(&format!("{}/input/grip/pose", config.hand))?) {
+            Some(grip) => grip,
+            None => {
+                println!("Ignoring space drag, {} has no grip pose", config.hand);
+                return Ok(());
+            }
+        };
+
+    let grip_space = session.create_action_space(&xr::ActionSpaceCreateInfo {
+        ty: xr::ActionSpaceCreateInfo::TYPE,
+        next: ptr::null(),
+        action: grip.action.handle,
+        subaction_path: grip.subaction_path,
+        pose_in_action_space: math::IDENTITY_POSE,
+    })?;
+
+    let base_space = match session.create_reference_space(&xr::ReferenceSpaceCreateInfo {
+        ty: xr::ReferenceSpaceCreateInfo::TYPE,
+        next: ptr::null(),
+        reference_space_type: xr::ReferenceSpaceType::LOCAL,
+        pose_in_reference_space: math::IDENTITY_POSE,
+    }) {
+        Ok(base_space) => base_space,
+        Err(result) => {
+            instance.destroy_space(grip_space)?;
+            return Err(result);
+        }
+    };
+
+    *space_drag = Some(SpaceDragState {
+        config: config.clone(),
+        chord,
+        grip_space,
+        base_space,
+        start: None,
+    });
+
+    Ok(())
+}
+
+///Moves the playspace while the chord is held, called after the god states have been synced
+pub fn update(session: &SessionWrapper, time: xr::Time) -> Result<()> {
+    let mut space_drag = session.space_drag.write().unwrap();
+    let state = match space_drag.as_mut() {
+        Some(state) => state,
+        None => return Ok(()),
+    };
+
+    let held = state.chord.iter().all(|binding| {
+        binding.is_active(session) && is_held(binding.action_state.read().unwrap().deref())
+    });
+
+    if !held {
+        if state.start.take().is_some() {
+            //Save where the user left the playspace
+            save_playspace(session);
+        }
+        return Ok(());
+    }
+
+    let grip_pose = match locate(session, state.grip_space, state.base_space, time)? {
+        Some(grip_pose) => grip_pose,
+        None => return Ok(()),
+    };
+
+    let (start_pose, start_offset) = match state.start {
+        Some(start) => start,
+        None => {
+            state.start = Some((grip_pose, *session.playspace_offset.read().unwrap()));
+            return Ok(());
+        }
+    };
+
+    let translation = match state.config.mode {
+        SpaceDragMode::Rotate => xr::Vector3f::default(),
+        _ => math::vec_sub(grip_pose.position, start_pose.position),
+    };
+    let yaw = match state.config.mode {
+        SpaceDragMode::Translate => 0.,
+        _ => math::quat_yaw(grip_pose.orientation) - math::quat_yaw(start_pose.orientation),
+    };
+
+    //Turn the playspace around where the hand started then move it along with the hand
+    let rotation = math::quat_from_euler_degrees([0., yaw.to_degrees(), 0.]);
+    let pivot = start_pose.position;
+    let drag = xr::Posef {
+        orientation: rotation,
+        position: math::vec_add(
+            math::vec_add(pivot, translation),
+            math::rotate(
+                rotation,
+                xr::Vector3f {
+                    x: -pivot.x,
+                    y: -pivot.y,
+                    z: -pivot.z,
+                },
+            ),
+        ),
+    };
+    let offset = math::pose_mul(&drag, &playspace_pose(&start_offset));
+
+    session.set_playspace_offset(PlayspaceOffset {
+        translation: [offset.position.x, offset.position.z],
+        height: offset.position.y,
+        yaw: math::quat_yaw(offset.orientation).to_degrees(),
+    });
+    Ok(())
+}
+
+///Writes the session's playspace offset to the application's settings
+pub fn save_playspace(session: &SessionWrapper) -> bool {
+    let instance = session.instance();
+    let settings = {
+        let mut settings = instance.settings.write().unwrap();
+        settings.playspace = Some(*session.playspace_offset.read().unwrap());
+        settings.clone()
+    };
+    try_write_json(&settings, Path::new(&instance.settings_path()))
+}
+
+fn is_held(state: &GodActionStateEnum) -> bool {
+    match state {
+        GodActionStateEnum::Boolean(state) => state.current_state,
+        GodActionStateEnum::Float(state) => state.current_state > 0.5,
+        GodActionStateEnum::Vector2f(state) => {
+            state.current_state.x * state.current_state.x
+                + state.current_state.y * state.current_state.y
+                > 0.25
+        }
+        GodActionStateEnum::Pose(state) => state.is_active,
+    }
+}
+
+fn locate(
+    session: &SessionWrapper,
+    space: xr::Space,
+    base_space: xr::Space,
+    time: xr::Time,
+) -> Result<Option<xr::Posef>> {
+    let mut location = xr::SpaceLocation {
+        ty: xr::SpaceLocation::TYPE,
+        next: ptr::null_mut(),
+        location_flags: xr::SpaceLocationFlags::EMPTY,
+        pose: math::IDENTITY_POSE,
+    };
+    util::check(unsafe {
+        (session.instance().core.locate_space)(space, base_space, time, &mut location)
+    })?;
+
+    let tracked =
+        xr::SpaceLocationFlags::POSITION_VALID | xr::SpaceLocationFlags::ORIENTATION_VALID;
+    Ok(if location.location_flags.contains(tracked) {
+        Some(location.pose)
+    } else {
+        None
+    })
+}
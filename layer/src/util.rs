@@ -38,4 +38,21 @@ pub fn check2<T>(result: xr::Result, out: T) -> Result<T> {
     } else {
         Ok(out)
     }
-}
\ No newline at end of file
+}
+///Writes a string using the two call idiom
+pub unsafe fn write_string(
+    value: &str,
+    buffer_capacity_input: u32,
+    buffer_count_output: *mut u32,
+    buffer: *mut std::os::raw::c_char,
+) -> xr::Result {
+    *buffer_count_output = value.len() as u32 + 1;
+    if buffer_capacity_input == 0 {
+        return xr::Result::SUCCESS;
+    }
+    if buffer_capacity_input < *buffer_count_output {
+        return xr::Result::ERROR_SIZE_INSUFFICIENT;
+    }
+    place_cstr(std::slice::from_raw_parts_mut(buffer, buffer_capacity_input as usize), value);
+    xr::Result::SUCCESS
+}
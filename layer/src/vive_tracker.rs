@@ -0,0 +1,79 @@
+use std::os::raw::c_void;
+
+use openxr::sys as xr;
+use openxr::Result;
+
+use crate::util;
+use crate::wrappers::*;
+
+pub const EXTENSION_NAME: &'static str = "XR_HTCX_vive_tracker_interaction";
+
+pub const PROFILE: &'static str = "/interaction_profiles/htc/vive_tracker_htcx";
+
+///XR_TYPE_VIVE_TRACKER_PATHS_HTCX, newer than the headers we build against
+pub const TYPE_VIVE_TRACKER_PATHS: xr::StructureType = xr::StructureType::from_raw(1000103000);
+///XR_TYPE_EVENT_DATA_VIVE_TRACKER_CONNECTED_HTCX
+pub const TYPE_EVENT_DATA_VIVE_TRACKER_CONNECTED: xr::StructureType =
+    xr::StructureType::from_raw(1000103001);
+
+pub const ROLES: [&'static str; 13] = [
+    "/user/vive_tracker_htcx/role/handheld_object",
+    "/user/vive_tracker_htcx/role/left_foot",
+    "/user/vive_tracker_htcx/role/right_foot",
+    "/user/vive_tracker_htcx/role/left_shoulder",
+    "/user/vive_tracker_htcx/role/right_shoulder",
+    "/user/vive_tracker_htcx/role/left_elbow",
+    "/user/vive_tracker_htcx/role/right_elbow",
+    "/user/vive_tracker_htcx/role/left_knee",
+    "/user/vive_tracker_htcx/role/right_knee",
+    "/user/vive_tracker_htcx/role/waist",
+    "/user/vive_tracker_htcx/role/chest",
+    "/user/vive_tracker_htcx/role/camera",
+    "/user/vive_tracker_htcx/role/keyboard",
+];
+
+///XrViveTrackerPathsHTCX
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct ViveTrackerPaths {
+    pub ty: xr::StructureType,
+    pub next: *mut c_void,
+    pub persistent_path: xr::Path,
+    ///XR_NULL_PATH if the tracker has not been given a role
+    pub role_path: xr::Path,
+}
+
+pub type EnumerateViveTrackerPaths = unsafe extern "system" fn(
+    instance: xr::Instance,
+    path_capacity_input: u32,
+    path_count_output: *mut u32,
+    paths: *mut ViveTrackerPaths,
+) -> xr::Result;
+
+///Asks the runtime which roles currently have a tracker, called whenever a tracker connects or a profile changes
+pub fn refresh_roles(instance: &InstanceWrapper) -> Result<()> {
+    let enumerate = match instance.enumerate_vive_tracker_paths {
+        Some(enumerate) => enumerate,
+        None => return Ok(()),
+    };
+
+    let empty = ViveTrackerPaths {
+        ty: TYPE_VIVE_TRACKER_PATHS,
+        next: std::ptr::null_mut(),
+        persistent_path: xr::Path::NULL,
+        role_path: xr::Path::NULL,
+    };
+
+    let mut count = 0;
+    util::check(unsafe { enumerate(instance.handle, 0, &mut count, std::ptr::null_mut()) })?;
+    let mut paths = vec![empty; count as usize];
+    util::check(unsafe { enumerate(instance.handle, count, &mut count, paths.as_mut_ptr()) })?;
+    paths.truncate(count as usize);
+
+    let mut roles = instance.vive_tracker_roles.write().unwrap();
+    for (role, connected) in roles.iter_mut() {
+        *connected = paths.iter().any(|paths| paths.role_path == *role);
+    }
+
+    Ok(())
+}
@@ -1,6 +1,10 @@
 pub mod space;
 pub mod session;
 
+use common::application_bindings::BindingModifiers;
+use common::application_settings::ApplicationSettings;
+use common::serial::CONFIG_DIR;
+use common::serial::get_uuid;
 use common::xrapplication_info::ActionType;
 use dashmap::DashMap;
 use once_cell::sync::OnceCell;
@@ -9,6 +13,8 @@ use openxr::sys as xr;
 use openxr::sys::pfn as pfn;
 
 use std::collections::HashMap;
+use std::collections::HashSet;
+use std::collections::VecDeque;
 use std::ffi::CString;
 use std::ops::Add;
 use std::ptr;
@@ -97,8 +103,32 @@ pub struct InstanceWrapper {
     pub exts: openxr::InstanceExtensions,
 
     pub get_instance_proc_addr_next: pfn::GetInstanceProcAddr,
+
+    ///The user's settings for this application
+    pub settings: RwLock<ApplicationSettings>,
+
+    ///Extensions enabled by the application which the layer implements instead of the runtime
+    pub layer_extensions: HashSet<&'static str>,
+
+    ///Runtime extensions the layer enabled for itself which the application did not ask for
+    pub hidden_extensions: HashSet<&'static str>,
+
+    ///Loaded when the runtime supports XR_HTCX_vive_tracker_interaction
+    pub enumerate_vive_tracker_paths: Option<crate::vive_tracker::EnumerateViveTrackerPaths>,
+
+    ///Every tracker role path and whether a tracker currently has that role
+    pub vive_tracker_roles: RwLock<HashMap<xr::Path, bool>>,
+
+    ///Events created by the layer, handed out by xrPollEvent before the runtime's
+    pub pending_events: RwLock<VecDeque<PendingEvent>>,
 }
 
+pub struct PendingEvent(pub xr::EventDataBuffer);
+
+//The layer only queues events with a null next pointer
+unsafe impl Send for PendingEvent {}
+unsafe impl Sync for PendingEvent {}
+
 #[derive(Debug)]
 pub struct ActionSetWrapper {
     pub handle: xr::ActionSet,
@@ -120,7 +150,14 @@ pub struct ActionWrapper {
     pub subaction_paths: Vec<xr::Path>,
     pub localized_name: String,
 
+    ///The bindings suggested by the application for each interaction profile
+    pub suggested_bindings: RwLock<HashMap<xr::Path, Vec<xr::Path>>>,
+    ///The binding modifications suggested by the application for each interaction profile, keyed by binding path
+    pub suggested_modifiers: RwLock<HashMap<xr::Path, HashMap<String, BindingModifiers>>>,
+    ///The suggested bindings with the user's changes applied, this is what the action is actually bound to
     pub bindings: RwLock<HashMap<xr::Path, Vec<xr::Path>>>,
+    ///Bindings the user has routed to another of the action's subaction paths, keyed by (interaction profile, binding)
+    pub binding_routes: RwLock<HashMap<(xr::Path, xr::Path), xr::Path>>,
 }
 
 impl std::fmt::Debug for InstanceWrapper {
@@ -134,6 +171,14 @@ impl std::fmt::Debug for InstanceWrapper {
 }
 
 impl InstanceWrapper {
+    pub fn layer_extension_enabled(&self, name: &str) -> bool {
+        self.layer_extensions.contains(name)
+    }
+
+    pub fn settings_path(&self) -> String {
+        format!("{}{}/settings.json", CONFIG_DIR, get_uuid(&self.application_name))
+    }
+
     #[inline]
     pub fn create_session(
         &self,
@@ -1,9 +1,16 @@
 use std::sync::Weak;
+use std::time::{Instant, SystemTime};
 
+use common::application_bindings::HapticRules;
+use common::application_settings::PlayspaceOffset;
 use openxr::sys as xr;
 
+use crate::capture::CaptureState;
+use crate::controller_model::LoadedControllerModel;
+use crate::eye_gaze;
 use crate::god_actions;
 use crate::path::*;
+use crate::space_drag::SpaceDragState;
 
 use super::*;
 
@@ -33,6 +40,39 @@ pub struct SessionWrapper {
     ///The bindings for each attached output action
     pub output_bindings: OnceCell<HashMap<xr::Action, RwLock<SubactionBindings<OutputBinding>>>>,
 
+    ///The user's haptic rules for each attached output action
+    pub haptic_rules: OnceCell<HashMap<xr::Action, RwLock<HashMap<xr::Path /* interactionProfile */, HapticRules>>>>,
+
+    ///When the user's bindings file was last modified and when that was last checked
+    pub user_bindings_modified: RwLock<(Option<SystemTime>, Option<Instant>)>,
+
+    ///When the application's settings file was last modified and when that was last checked
+    pub settings_modified: RwLock<(Option<SystemTime>, Option<Instant>)>,
+
+    ///Modification time of the capture request file
+    pub capture_modified: RwLock<(Option<SystemTime>, Option<Instant>)>,
+
+    ///Waiting for the user to press the input they want bound
+    pub capture: RwLock<Option<CaptureState>>,
+
+    ///The offset applied to every STAGE and LOCAL space
+    pub playspace_offset: RwLock<PlayspaceOffset>,
+
+    ///Meters the LOCAL origin is above the floor, used for emulated LOCAL_FLOOR spaces
+    pub local_floor_height: RwLock<f32>,
+
+    ///Set when the floor height needs to be measured again (checked every xrWaitFrame)
+    pub local_floor_dirty: RwLock<bool>,
+
+    ///Moves the playspace offset while the user holds a chord
+    pub space_drag: RwLock<Option<SpaceDragState>>,
+
+    ///Models handed out through XR_MSFT_controller_model
+    pub controller_models: RwLock<Vec<LoadedControllerModel>>,
+
+    ///User path and interaction profile pairs which have no model so the disk is only checked once for them
+    pub missing_controller_models: RwLock<HashSet<(xr::Path, xr::Path)>>,
+
     ///The cached state of the attached application actions (updated every sync call)
     pub cached_action_states: OnceCell<HashMap<xr::Action, RwLock<CachedActionStatesEnum>>>,
 
@@ -41,7 +81,19 @@ pub struct SessionWrapper {
 
     pub active_profiles: HashMap<TopLevelUserPath, RwLock<InteractionProfilePath>>,
 
+    ///Devices on blended user paths which reported input during the latest sync
+    pub blended_devices: RwLock<HashSet<(InteractionProfilePath, TopLevelUserPath)>>,
+
     pub sync_idx: RwLock<u64>,
+
+    ///The predicted display time returned by the latest xrWaitFrame call
+    pub predicted_display_time: RwLock<xr::Time>,
+
+    ///Whether the layer reported the emulated gamepad as connected on the latest sync
+    pub emulating_gamepad: RwLock<bool>,
+
+    ///The predicted display time used by the previous sync call
+    pub last_sync_time: RwLock<Option<xr::Time>>,
 }
 
 impl SessionWrapper {
@@ -49,6 +101,9 @@ impl SessionWrapper {
         let mut wrapper = SessionWrapper {
             handle,
             instance: Arc::downgrade(instance),
+            playspace_offset: RwLock::new(
+                instance.settings.read().unwrap().playspace.unwrap_or_default(),
+            ),
             ..Default::default()
         };
 
@@ -70,6 +125,20 @@ impl SessionWrapper {
                 RwLock::new(InteractionProfilePath(xr::Path::NULL)),
             );
         }
+        for role in instance.vive_tracker_roles.read().unwrap().keys() {
+            wrapper.active_profiles.insert(
+                TopLevelUserPath(*role),
+                RwLock::new(InteractionProfilePath(xr::Path::NULL)),
+            );
+        }
+        if instance.layer_extension_enabled(eye_gaze::EXTENSION_NAME) {
+            wrapper.active_profiles.insert(
+                TopLevelUserPath(instance.string_to_path(eye_gaze::USER_PATH)?),
+                RwLock::new(InteractionProfilePath(
+                    instance.string_to_path(eye_gaze::PROFILE)?,
+                )),
+            );
+        }
 
         //Create session specific input / output states for each god action
         for (profile_name, god_action_set) in &instance.god_action_sets {
@@ -134,6 +203,7 @@ impl SessionWrapper {
             .god_action_sets
             .values()
             .map(|container| container.handle)
+            .filter(|handle| *handle != xr::ActionSet::NULL)
             .collect::<Vec<_>>();
 
         let attach_info = xr::SessionActionSetsAttachInfo {
@@ -158,13 +228,63 @@ impl SessionWrapper {
         interaction_profile: InteractionProfilePath,
         top_level_user_path: TopLevelUserPath,
     ) -> bool {
-        interaction_profile
-            == *self
-                .active_profiles
-                .get(&top_level_user_path)
-                .unwrap()
+        //Devices on user paths the session doesn't track a profile for are never filtered out
+        self.active_profiles
+            .get(&top_level_user_path)
+            .map_or(true, |active_profile| interaction_profile == *active_profile.read().unwrap())
+            || self
+                .blended_devices
                 .read()
                 .unwrap()
+                .contains(&(interaction_profile, top_level_user_path))
+    }
+
+    ///Every device on a blended user path which has an active input is treated as active
+    pub fn update_blended_devices(&self) {
+        let instance = self.instance();
+        let blend_devices = instance
+            .settings
+            .read()
+            .unwrap()
+            .blend_devices
+            .iter()
+            .filter_map(|user_path| instance.string_to_path(user_path).ok())
+            .collect::<Vec<_>>();
+        self.blend_devices(&blend_devices);
+    }
+
+    ///A device is only seen through the isActive of its inputs as reported by the runtime,
+    ///so a runtime which deactivates every input outside a user path's current interaction profile leaves that profile the only one blended
+    pub fn blend_devices(&self, blend_devices: &[xr::Path]) {
+        let mut blended_devices = self.blended_devices.write().unwrap();
+        blended_devices.clear();
+        if blend_devices.is_empty() {
+            return;
+        }
+
+        for (profile, god_states) in &self.god_states {
+            for god_state in god_states.values() {
+                if blend_devices.contains(&god_state.subaction_path)
+                    && Arc::strong_count(god_state) > 1
+                    && god_state.action_state.read().unwrap().get_inner().is_active()
+                {
+                    blended_devices.insert((
+                        InteractionProfilePath(*profile),
+                        TopLevelUserPath(god_state.subaction_path),
+                    ));
+                }
+            }
+        }
+    }
+
+    ///Moves every STAGE and LOCAL space of the session
+    pub fn set_playspace_offset(&self, offset: PlayspaceOffset) {
+        *self.playspace_offset.write().unwrap() = offset;
+    }
+
+    ///Moves every emulated LOCAL_FLOOR space of the session
+    pub fn set_local_floor_height(&self, height: f32) {
+        *self.local_floor_height.write().unwrap() = height;
     }
 
     #[inline]
@@ -182,6 +302,34 @@ impl SessionWrapper {
         unsafe { (self.instance().core.attach_session_action_sets)(self.handle, attach_info) }
     }
 
+    #[inline]
+    pub fn create_reference_space(
+        &self,
+        create_info: *const xr::ReferenceSpaceCreateInfo,
+    ) -> Result<xr::Space> {
+        let mut space = xr::Space::NULL;
+        util::check2(
+            unsafe {
+                (self.instance().core.create_reference_space)(self.handle, create_info, &mut space)
+            },
+            space,
+        )
+    }
+
+    #[inline]
+    pub fn wait_frame(
+        &self,
+        frame_wait_info: *const xr::FrameWaitInfo,
+        frame_state: *mut xr::FrameState,
+    ) -> xr::Result {
+        unsafe { (self.instance().core.wait_frame)(self.handle, frame_wait_info, frame_state) }
+    }
+
+    #[inline]
+    pub fn end_frame(&self, frame_end_info: *const xr::FrameEndInfo) -> xr::Result {
+        unsafe { (self.instance().core.end_frame)(self.handle, frame_end_info) }
+    }
+
     #[inline]
     pub fn sync_actions(&self, sync_info: *const xr::ActionsSyncInfo) -> xr::Result {
         unsafe { (self.instance().core.sync_actions)(self.handle, sync_info) }
@@ -1,8 +1,16 @@
 use std::{ops::Deref, sync::Weak};
 
+use common::application_bindings::PoseOffset;
+use common::application_settings::PlayspaceOffset;
 use openxr::sys as xr;
 
 use crate::god_actions;
+use crate::god_actions::EmulatedSource;
+use crate::god_actions::UserBindings;
+use crate::local_floor;
+use crate::math;
+use crate::palm_pose;
+use crate::pose_filter::PoseFilterStates;
 
 use super::*;
 
@@ -15,7 +23,12 @@ pub struct SpaceWrapper {
 
 pub enum SpaceType {
     ACTION(Arc<ActionSpace>),
-    REFERENCE,
+    REFERENCE(Arc<ReferenceSpace>),
+}
+
+pub struct ReferenceSpace {
+    pub reference_space_type: xr::ReferenceSpaceType,
+    pub pose_in_reference_space: xr::Posef,
 }
 
 pub struct ActionSpace {
@@ -26,11 +39,18 @@ pub struct ActionSpace {
     pub sync_idx: RwLock<u64>,
 
     pub cur_binding: RwLock<Option<ActionSpaceBinding>>,
+
+    ///Reset whenever the binding changes so poses from different devices are never blended
+    pub filter_states: RwLock<PoseFilterStates>,
 }
 
 pub struct ActionSpaceBinding {
     pub space_handle: xr::Space,
     pub binding: Arc<InputBinding>,
+    ///The user's offset the space was created with
+    pub pose_offset: Option<PoseOffset>,
+    ///The binding's user path, which the user's pose filters are set for
+    pub user_path: String,
 }
 
 impl SpaceWrapper {
@@ -50,7 +70,15 @@ impl SpaceWrapper {
                     None
                 }
             }
-            SpaceType::REFERENCE => Some(self.unchecked_handle),
+            SpaceType::REFERENCE(_) => Some(self.unchecked_handle),
+        }
+    }
+
+    ///The pose of the application's space within the one returned by get_handle, None if they are the same
+    pub fn correction(&self) -> Option<xr::Posef> {
+        match &self.ty {
+            SpaceType::ACTION(_) => None,
+            SpaceType::REFERENCE(reference_space) => reference_space.correction(&self.session()),
         }
     }
 
@@ -60,25 +88,87 @@ impl SpaceWrapper {
     }
 }
 
+impl ReferenceSpace {
+    ///The pose of the application's space within the runtime's copy of it, None if they are the same
+    ///The playspace offset and floor height are applied by the layer rather than baked into new runtime spaces so they can change every frame
+    ///STAGE, LOCAL and emulated LOCAL_FLOOR spaces are the only ones moved
+    pub fn correction(&self, session: &SessionWrapper) -> Option<xr::Posef> {
+        let offset = *session.playspace_offset.read().unwrap();
+
+        let pose = if self.reference_space_type == local_floor::REFERENCE_SPACE_TYPE_LOCAL_FLOOR
+            && session.instance().layer_extension_enabled(local_floor::EXTENSION_NAME)
+        {
+            //The runtime's copy is a LOCAL space
+            let floor_pose = xr::Posef {
+                position: xr::Vector3f {
+                    x: 0.,
+                    y: -*session.local_floor_height.read().unwrap(),
+                    z: 0.,
+                },
+                ..math::IDENTITY_POSE
+            };
+            math::pose_mul(&playspace_pose(&offset), &floor_pose)
+        } else if offset != PlayspaceOffset::default()
+            && (self.reference_space_type == xr::ReferenceSpaceType::STAGE
+                || self.reference_space_type == xr::ReferenceSpaceType::LOCAL
+                || self.reference_space_type == local_floor::REFERENCE_SPACE_TYPE_LOCAL_FLOOR)
+        {
+            playspace_pose(&offset)
+        } else {
+            return None;
+        };
+
+        //The runtime's copy already has pose_in_reference_space applied
+        Some(math::pose_mul(
+            &math::pose_inverse(&self.pose_in_reference_space),
+            &math::pose_mul(&pose, &self.pose_in_reference_space),
+        ))
+    }
+}
+
+///The pose of the application's playspace within the runtime's
+pub fn playspace_pose(offset: &PlayspaceOffset) -> xr::Posef {
+    xr::Posef {
+        orientation: math::quat_from_euler_degrees([0., offset.yaw, 0.]),
+        position: xr::Vector3f {
+            x: offset.translation[0],
+            y: offset.height,
+            z: offset.translation[1],
+        },
+    }
+}
+
 impl ActionSpace {
     pub fn sync(
         &self,
         session: &SessionWrapper,
         sync_idx: u64,
         subaction_bindings: &SubactionBindings<InputBinding>,
+        user_bindings: &UserBindings,
     ) -> Result<()> {
         let instance = session.instance();
 
         *self.sync_idx.write().unwrap() = sync_idx;
 
+        let get_pose_offset = |binding: &InputBinding| {
+            user_bindings
+                .get(&binding.action.profile_name)
+                .and_then(|action_bindings| action_bindings.modifiers.get(&binding.binding_str))
+                .and_then(|modifiers| modifiers.pose_offset)
+        };
+
         let mut cur_binding = self.cur_binding.write().unwrap();
         if let Some(cur_binding) = cur_binding.as_ref() {
             match cur_binding.binding.action_state.read().unwrap().deref() {
                 god_actions::GodActionStateEnum::Pose(state) => {
-                    if state.is_active {
+                    //The space is recreated if the user has changed its offset since it was created
+                    if state.is_active
+                        && cur_binding.pose_offset == get_pose_offset(&cur_binding.binding)
+                    {
                         return Ok(());
                     } else {
                         instance.destroy_space(cur_binding.space_handle)?;
+                        self.filter_states.write().unwrap().clear();
                     }
                 }
                 _ => panic!("Action space somehow bound to non-pose action"),
@@ -97,15 +187,54 @@ impl ActionSpace {
         });
 
         if let Some(binding) = binding {
-            *cur_binding = Some(ActionSpaceBinding {
-                space_handle: session.create_action_space(&xr::ActionSpaceCreateInfo {
+            let pose_offset = get_pose_offset(binding);
+            let mut pose_in_action_space = match &pose_offset {
+                Some(pose_offset) => math::pose_mul(
+                    &xr::Posef {
+                        orientation: math::quat_from_euler_degrees(pose_offset.rotation),
+                        position: xr::Vector3f {
+                            x: pose_offset.position[0],
+                            y: pose_offset.position[1],
+                            z: pose_offset.position[2],
+                        },
+                    },
+                    &self.pose_in_action_space,
+                ),
+                None => self.pose_in_action_space,
+            };
+            if binding.action.emulated == Some(EmulatedSource::PalmPose) {
+                let right_hand =
+                    instance.path_to_string(binding.subaction_path)? == "/user/hand/right";
+                pose_in_action_space = math::pose_mul(
+                    &palm_pose::palm_in_grip(&binding.action.profile_name_str, right_hand),
+                    &pose_in_action_space,
+                );
+            }
+
+            let space_handle = match binding.action.emulated {
+                Some(EmulatedSource::HeadGaze) => {
+                    session.create_reference_space(&xr::ReferenceSpaceCreateInfo {
+                        ty: xr::ReferenceSpaceCreateInfo::TYPE,
+                        next: ptr::null(),
+                        reference_space_type: xr::ReferenceSpaceType::VIEW,
+                        pose_in_reference_space: pose_in_action_space,
+                    })?
+                }
+                _ => session.create_action_space(&xr::ActionSpaceCreateInfo {
                     ty: xr::ActionSpaceCreateInfo::TYPE,
                     next: ptr::null(),
                     action: binding.action.handle,
-                    subaction_path: self.subaction_path,
-                    pose_in_action_space: self.pose_in_action_space,
+                    //God actions cover every user path of their profile so the binding's is used to pick the device
+                    subaction_path: binding.subaction_path,
+                    pose_in_action_space,
                 })?,
+            };
+
+            *cur_binding = Some(ActionSpaceBinding {
+                space_handle,
                 binding: (*binding).clone(),
+                pose_offset,
+                user_path: instance.path_to_string(binding.subaction_path)?,
             })
         } else {
             *cur_binding = None